@@ -0,0 +1,92 @@
+use crate::{angle_end, error, is_ident, is_punct, where_start};
+use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Turns `#[impl_twice(targets)] impl<...> Type where ... { body }` into
+/// `impl_twice!(impl<...> Type, targets where ... { body })`, with the
+/// where clause before any other `impl` in the targets. Every token
+/// is moved over as it is, so errors still point at the code they came
+/// from. The macro is `::impl_twice::impl_twice!`, unless the arguments
+/// start with `crate = path,` for a crate that is somewhere else.
+pub(crate) fn impl_twice(args: TokenStream, item: TokenStream) -> Result<TokenStream, TokenStream> {
+    let (krate, args) = crate_path(args)?;
+    let mut tokens: Vec<TokenTree> = item.into_iter().collect();
+    let body = match tokens.pop() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        other => {
+            let span = other.map_or_else(Span::call_site, |token| token.span());
+            return Err(error(span, "`#[impl_twice(...)]` only goes on impl blocks"));
+        }
+    };
+
+    // Attributes are `#` and a group, so the first `impl` is the keyword.
+    let keyword = tokens
+        .iter()
+        .position(|token| is_ident(token, "impl"))
+        .ok_or_else(|| error(body.span(), "`#[impl_twice(...)]` only goes on impl blocks"))?;
+
+    let mut start = keyword + 1;
+    if tokens.get(start).is_some_and(|token| is_punct(token, '<')) {
+        start = match angle_end(&tokens, start) {
+            Some(end) => end + 1,
+            None => return Err(error(tokens[start].span(), "unclosed `<` in the generic parameters")),
+        };
+    }
+    let end = where_start(&tokens, start).unwrap_or(tokens.len());
+
+    // The where clause goes on the types of the first group, which ends
+    // where the attribute starts a group of its own with another `impl`.
+    let group_end = next_group(&args);
+    let mut input: Vec<TokenTree> = tokens[..end].to_vec();
+    if group_end > 0 {
+        input.push(TokenTree::Punct(Punct::new(',', Spacing::Alone)));
+        input.extend(args[..group_end].iter().cloned());
+    }
+    input.extend(tokens[end..].iter().cloned());
+    input.extend(args[group_end..].iter().cloned());
+    input.push(TokenTree::Group(body));
+
+    let mut output = krate;
+    output.push(TokenTree::Punct(Punct::new(':', Spacing::Joint)));
+    output.push(TokenTree::Punct(Punct::new(':', Spacing::Alone)));
+    output.push(TokenTree::Ident(Ident::new("impl_twice", Span::call_site())));
+    output.push(TokenTree::Punct(Punct::new('!', Spacing::Alone)));
+    output.push(TokenTree::Group(Group::new(
+        Delimiter::Brace,
+        input.into_iter().collect(),
+    )));
+    Ok(output.into_iter().collect())
+}
+
+/// Finds where the next group starts in the arguments of the attribute,
+/// which is at its `impl`, along with the `unsafe` and the attributes
+/// right before it.
+fn next_group(args: &[TokenTree]) -> usize {
+    let Some(mut start) = args.iter().position(|token| is_ident(token, "impl")) else {
+        return args.len();
+    };
+    if start > 0 && is_ident(&args[start - 1], "unsafe") {
+        start -= 1;
+    }
+    while start > 1 && is_punct(&args[start - 2], '#') && matches!(&args[start - 1], TokenTree::Group(_)) {
+        start -= 2;
+    }
+    start
+}
+
+/// Splits the path of the crate from the arguments of the attribute, which
+/// is `::impl_twice` unless they start with `crate = path,`.
+fn crate_path(args: TokenStream) -> Result<(Vec<TokenTree>, Vec<TokenTree>), TokenStream> {
+    let args: Vec<TokenTree> = args.into_iter().collect();
+    if !(args.first().is_some_and(|token| is_ident(token, "crate")) && args.get(1).is_some_and(|token| is_punct(token, '='))) {
+        let krate = vec![
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("impl_twice", Span::call_site())),
+        ];
+        return Ok((krate, args));
+    }
+    match args.iter().position(|token| is_punct(token, ',')) {
+        Some(comma) if comma > 2 => Ok((args[2..comma].to_vec(), args[comma + 1..].to_vec())),
+        _ => Err(error(args[0].span(), "expected `crate = path` to be followed by a `,` and the other types, like `crate = my_crate::impl_twice, Other`")),
+    }
+}
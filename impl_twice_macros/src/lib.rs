@@ -0,0 +1,219 @@
+#![deny(rust_2018_idioms, clippy::all, clippy::pedantic)]
+
+//! Procedural macros for `impl_twice`. They are used by the macros of
+//! `impl_twice` when its features ask for them, and aren't meant to be
+//! used on their own.
+
+mod attribute;
+mod lift;
+mod paste;
+mod twice;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Implements the impl block it's put on for the types listed in the
+/// attribute as well, the same way as `impl_twice!`.
+///
+/// ```ignore
+/// #[impl_twice(WrappedSliceMut<'_, T>)]
+/// impl<T> WrappedSlice<'_, T> {
+///     pub fn get(&self, index: usize) -> Option<&'_ T> {
+///         self.0.get(index)
+///     }
+/// }
+/// ```
+///
+/// is the same as
+///
+/// ```ignore
+/// impl_twice!(
+///     impl<T> WrappedSlice<'_, T>, WrappedSliceMut<'_, T> {
+///         pub fn get(&self, index: usize) -> Option<&'_ T> {
+///             self.0.get(index)
+///         }
+///     }
+/// );
+/// ```
+///
+/// The attribute takes anything that can follow the first type in
+/// `impl_twice!`, so the types in it can be tagged with `mut`, have
+/// overrides and bindings after a `=>`, and be followed by an `impl` with
+/// generics of its own. Since the impl block has to be valid rust on its
+/// own, the body can't use placeholders like `#mut`, but `#[only(...)]`,
+/// `#[except(...)]` and `#[twice]` work.
+///
+/// The attribute expands to `::impl_twice::impl_twice!`. When `impl_twice`
+/// is somewhere else, like in a re-export, its path goes first in the
+/// attribute, as in `#[impl_twice(crate = my_crate::impl_twice, Other)]`.
+#[proc_macro_attribute]
+pub fn impl_twice(args: TokenStream, item: TokenStream) -> TokenStream {
+    attribute::impl_twice(args, item).unwrap_or_else(|error| error)
+}
+
+/// Declares the mutable counterpart of the struct it's put on, named by
+/// `mut = Name`. Every field that is a shared reference, like `&'a T`, is
+/// a mutable reference in the other struct, like `&'a mut T`, and the
+/// rest of the struct is the same, so the two can't drift apart.
+///
+/// ```ignore
+/// #[twice(mut = WrappedSliceMut)]
+/// #[derive(Clone, Copy, Debug)]
+/// pub struct WrappedSlice<'a, T>(&'a [T]);
+/// ```
+///
+/// is the same as
+///
+/// ```ignore
+/// #[derive(Clone, Copy, Debug)]
+/// pub struct WrappedSlice<'a, T>(&'a [T]);
+///
+/// /// The mutable counterpart of [`WrappedSlice`].
+/// #[derive(Debug)]
+/// pub struct WrappedSliceMut<'a, T>(&'a mut [T]);
+/// ```
+///
+/// Only the outermost reference of a field is made mutable, so a field
+/// that starts with `&&` is an error, since that reference has no
+/// lifetime to go with the `mut`; it's written `&'a &'b T`. `Clone` and
+/// `Copy` are left out of the derives of the mutable struct, since
+/// mutable references are neither, and so are doc comments.
+#[proc_macro_attribute]
+pub fn twice(args: TokenStream, item: TokenStream) -> TokenStream {
+    twice::twice(args, item).unwrap_or_else(|error| error)
+}
+
+/// Implements the impl block it's put on for the mutable type in the
+/// attribute as well, lifting the methods that take `&self` and return
+/// references.
+///
+/// ```ignore
+/// #[lift_mut(WrappedSliceMut<'_, T>)]
+/// impl<T> WrappedSlice<'_, T> {
+///     pub fn len(&self) -> usize {
+///         self.0.len()
+///     }
+///
+///     pub fn get(&self, index: usize) -> Option<&T> {
+///         self.0.get(index)
+///     }
+/// }
+/// ```
+///
+/// is the same as
+///
+/// ```ignore
+/// impl<T> WrappedSlice<'_, T> {
+///     pub fn len(&self) -> usize {
+///         self.0.len()
+///     }
+///
+///     pub fn get(&self, index: usize) -> Option<&T> {
+///         self.0.get(index)
+///     }
+/// }
+///
+/// impl<T> WrappedSliceMut<'_, T> {
+///     pub fn len(&self) -> usize {
+///         self.0.len()
+///     }
+///
+///     pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+///         self.0.get_mut(index)
+///     }
+/// }
+/// ```
+///
+/// A lifted method takes `&mut self`, its return type has `&mut` where it
+/// had `&`, and `&self` in its body is `&mut self`. The method, and the
+/// methods its body calls on `self`, are renamed with a table of the
+/// methods in the standard library that have a `_mut` counterpart, like
+/// `get`, `iter`, `first` and `as_ref`. More names go after the type, as in
+/// `#[lift_mut(TreeMut<'_>, rename(node = node_mut, children = children_mut))]`.
+///
+/// A method can't be lifted if it returns a reference that doesn't borrow
+/// from `self`, or a reference to another reference, since it's not clear
+/// which one should be mutable. That's an error pointing at the
+/// reference, and so is lifting two methods to the same name, or calling
+/// a renamed method on something that isn't `self` but mentions it, like
+/// `Self::items(self).get(0)`. Other methods and items are copied as they
+/// are.
+#[proc_macro_attribute]
+pub fn lift_mut(args: TokenStream, item: TokenStream) -> TokenStream {
+    lift::lift_mut(args, item).unwrap_or_else(|error| error)
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn __impl_twice_paste(input: TokenStream) -> TokenStream {
+    paste::paste(input).unwrap_or_else(|error| error)
+}
+
+/// A `compile_error!` with the message, pointing at the span.
+fn error(span: Span, message: &str) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut tokens = Vec::new();
+    for part in &["core", "compile_error"] {
+        let mut first = Punct::new(':', Spacing::Joint);
+        first.set_span(span);
+        let mut second = Punct::new(':', Spacing::Alone);
+        second.set_span(span);
+        tokens.push(TokenTree::Punct(first));
+        tokens.push(TokenTree::Punct(second));
+        tokens.push(TokenTree::Ident(Ident::new(part, span)));
+    }
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    tokens.push(TokenTree::Punct(bang));
+    let mut group = Group::new(Delimiter::Brace, TokenTree::Literal(message).into());
+    group.set_span(span);
+    tokens.push(TokenTree::Group(group));
+    tokens.into_iter().collect()
+}
+
+pub(crate) fn is_ident(token: &TokenTree, name: &str) -> bool {
+    matches!(token, TokenTree::Ident(ident) if ident.to_string() == name)
+}
+
+pub(crate) fn is_punct(token: &TokenTree, c: char) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == c)
+}
+
+/// Whether the `>` at `i` is the end of a `->`, and not a closing bracket.
+pub(crate) fn is_arrow(tokens: &[TokenTree], i: usize) -> bool {
+    i > 0
+        && matches!(&tokens[i - 1], TokenTree::Punct(punct)
+            if punct.as_char() == '-' && punct.spacing() == Spacing::Joint)
+}
+
+/// Finds the `>` that closes the `<` at `start`.
+pub(crate) fn angle_end(tokens: &[TokenTree], start: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        if is_punct(token, '<') {
+            depth += 1;
+        } else if is_punct(token, '>') && !is_arrow(tokens, i) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Finds the `where` of an impl or function, which isn't inside of generic
+/// arguments.
+pub(crate) fn where_start(tokens: &[TokenTree], start: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        if is_punct(token, '<') {
+            depth += 1;
+        } else if is_punct(token, '>') && !is_arrow(tokens, i) {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && is_ident(token, "where") {
+            return Some(i);
+        }
+    }
+    None
+}
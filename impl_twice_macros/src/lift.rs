@@ -0,0 +1,466 @@
+use crate::{angle_end, error, is_arrow, is_ident, is_punct, where_start};
+use proc_macro::{Delimiter, Group, Ident, Spacing, Span, TokenStream, TokenTree};
+
+/// The methods that are renamed when nothing else is said, which are the
+/// ones of the standard library that have a `_mut` counterpart.
+const RENAMES: &[(&str, &str)] = &[
+    ("as_deref", "as_deref_mut"),
+    ("as_ptr", "as_mut_ptr"),
+    ("as_ref", "as_mut"),
+    ("as_slice", "as_mut_slice"),
+    ("borrow", "borrow_mut"),
+    ("chunks", "chunks_mut"),
+    ("chunks_exact", "chunks_exact_mut"),
+    ("first", "first_mut"),
+    ("get", "get_mut"),
+    ("get_unchecked", "get_unchecked_mut"),
+    ("iter", "iter_mut"),
+    ("last", "last_mut"),
+    ("split_at", "split_at_mut"),
+    ("split_first", "split_first_mut"),
+    ("split_last", "split_last_mut"),
+    ("values", "values_mut"),
+];
+
+/// Pairs of a method and the name it has in the mutable impl.
+type Renames = Vec<(String, String)>;
+
+/// Emits the impl block it's put on as it is, along with the same impl
+/// for the mutable type, where the methods that take `&self` and return
+/// references are lifted. A lifted method takes `&mut self`, returns
+/// mutable references, borrows `self` mutably in its body, and is renamed
+/// with the rename table, as are the methods it calls. Everything else is
+/// copied as it is.
+pub(crate) fn lift_mut(args: TokenStream, item: TokenStream) -> Result<TokenStream, TokenStream> {
+    let (target, renames) = lift_args(args)?;
+    let mut tokens: Vec<TokenTree> = item.clone().into_iter().collect();
+    let body = match tokens.pop() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        other => {
+            let span = other.map_or_else(Span::call_site, |token| token.span());
+            return Err(error(span, "`#[lift_mut(...)]` only goes on impl blocks"));
+        }
+    };
+
+    // Attributes are `#` and a group, so the first `impl` is the keyword.
+    let keyword = tokens
+        .iter()
+        .position(|token| is_ident(token, "impl"))
+        .ok_or_else(|| error(body.span(), "`#[lift_mut(...)]` only goes on impl blocks"))?;
+    let mut start = keyword + 1;
+    if tokens.get(start).is_some_and(|token| is_punct(token, '<')) {
+        start = match angle_end(&tokens, start) {
+            Some(end) => end + 1,
+            None => return Err(error(tokens[start].span(), "unclosed `<` in the generic parameters")),
+        };
+    }
+    let end = where_start(&tokens, start).unwrap_or(tokens.len());
+    if let Some(token) = tokens[start..end].iter().find(|token| is_ident(token, "for")) {
+        return Err(error(
+            token.span(),
+            "`#[lift_mut(...)]` only works on inherent impls, since a trait decides the signatures of its methods",
+        ));
+    }
+
+    let items: Vec<TokenTree> = body.stream().into_iter().collect();
+    let mut lifted = Vec::with_capacity(items.len());
+    let mut names: Vec<Ident> = Vec::new();
+    for item in split_items(&items) {
+        let name = if let Some((lifted_item, name)) = lift_item(item, &renames)? {
+            lifted.extend(lifted_item);
+            name
+        } else {
+            lifted.extend(item.iter().cloned());
+            match fn_keyword(item).and_then(|keyword| item.get(keyword + 1)) {
+                Some(TokenTree::Ident(name)) => name.clone(),
+                _ => continue,
+            }
+        };
+        if names.iter().any(|other| other.to_string() == name.to_string()) {
+            return Err(error(
+                name.span(),
+                &format!("lifting makes a second `{name}` in the impl for the mutable type"),
+            ));
+        }
+        names.push(name);
+    }
+
+    let mut output = item;
+    output.extend(tokens[..start].iter().cloned());
+    output.extend(target);
+    output.extend(tokens[end..].iter().cloned());
+    let mut lifted = Group::new(Delimiter::Brace, lifted.into_iter().collect());
+    lifted.set_span(body.span());
+    output.extend(Some(TokenTree::Group(lifted)));
+    Ok(output)
+}
+
+/// Reads the mutable type, and the `rename(name = name_mut, ...)` after
+/// it, which adds to the default rename table.
+fn lift_args(args: TokenStream) -> Result<(Vec<TokenTree>, Renames), TokenStream> {
+    let mut args: Vec<TokenTree> = args.into_iter().collect();
+    let mut renames: Renames = RENAMES
+        .iter()
+        .map(|&(shared, mutable)| (shared.to_owned(), mutable.to_owned()))
+        .collect();
+    if let [.., TokenTree::Punct(comma), TokenTree::Ident(keyword), TokenTree::Group(table)] = args.as_slice() {
+        if comma.as_char() == ',' && keyword.to_string() == "rename" && table.delimiter() == Delimiter::Parenthesis {
+            let table: Vec<TokenTree> = table.stream().into_iter().collect();
+            for entry in table.split(|token| is_punct(token, ',')).filter(|entry| !entry.is_empty()) {
+                match entry {
+                    [TokenTree::Ident(shared), TokenTree::Punct(eq), TokenTree::Ident(mutable)] if eq.as_char() == '=' => {
+                        renames.retain(|(name, _)| *name != shared.to_string());
+                        renames.push((shared.to_string(), mutable.to_string()));
+                    }
+                    _ => return Err(error(entry[0].span(), "expected `name = name_mut` in the rename table")),
+                }
+            }
+            args.truncate(args.len() - 3);
+        }
+    }
+    if args.is_empty() {
+        return Err(error(Span::call_site(), "expected the mutable type, like `#[lift_mut(SliceMut<'_, T>)]`"));
+    }
+    Ok((args, renames))
+}
+
+/// Splits the items of an impl, which end at a `;`, or at the body of a
+/// function or of a macro call.
+fn split_items(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let end = match token {
+            TokenTree::Punct(semi) => semi.as_char() == ';',
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                let item = &tokens[start..i];
+                fn_keyword(item).is_some() || item.last().is_some_and(|token| is_punct(token, '!'))
+            }
+            _ => false,
+        };
+        if end {
+            items.push(&tokens[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < tokens.len() {
+        items.push(&tokens[start..]);
+    }
+    items
+}
+
+/// Finds the `fn` of a function, after its attributes and qualifiers.
+fn fn_keyword(item: &[TokenTree]) -> Option<usize> {
+    let mut i = 0;
+    loop {
+        match item.get(i)? {
+            TokenTree::Punct(hash) if hash.as_char() == '#' => i += 2,
+            TokenTree::Ident(ident) if ident.to_string() == "fn" => return Some(i),
+            TokenTree::Ident(ident)
+                if ["pub", "const", "unsafe", "async", "extern", "default"].contains(&ident.to_string().as_str()) =>
+            {
+                i += 1;
+            }
+            // `pub(crate)` and `extern "C"`.
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => i += 1,
+            TokenTree::Literal(_) => i += 1,
+            _ => return None,
+        }
+    }
+}
+
+/// Lifts a function that takes `&self` and returns references, giving
+/// back the lifted function and its new name, or `None` for any other
+/// item.
+fn lift_item(item: &[TokenTree], renames: &[(String, String)]) -> Result<Option<(Vec<TokenTree>, Ident)>, TokenStream> {
+    let Some(keyword) = fn_keyword(item) else {
+        return Ok(None);
+    };
+    let Some(TokenTree::Ident(name)) = item.get(keyword + 1) else {
+        return Ok(None);
+    };
+    let Some(params) = (keyword + 2..item.len())
+        .find(|&i| matches!(&item[i], TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis))
+    else {
+        return Ok(None);
+    };
+    let (TokenTree::Group(params_group), Some(TokenTree::Group(body))) = (&item[params], item.last()) else {
+        return Ok(None);
+    };
+    let param_tokens: Vec<TokenTree> = params_group.stream().into_iter().collect();
+    let Some((self_lifetime, self_index)) = shared_self(&param_tokens) else {
+        return Ok(None);
+    };
+
+    // The return type is everything from the `->` to the where clause or
+    // the body.
+    let body_index = item.len() - 1;
+    if !(params + 2 < body_index && is_punct(&item[params + 1], '-') && is_arrow(item, params + 2)) {
+        return Ok(None);
+    }
+    let ret_start = params + 3;
+    let ret_end = where_start(&item[..body_index], ret_start).unwrap_or(body_index);
+    let ret = &item[ret_start..ret_end];
+    if !has_reference(ret, name, self_lifetime.as_deref())? {
+        return Ok(None);
+    }
+
+    let new_name = match renames.iter().find(|(shared, _)| *shared == name.to_string()) {
+        Some((_, mutable)) => Ident::new(mutable, name.span()),
+        None => name.clone(),
+    };
+    let mut lifted: Vec<TokenTree> = item[..=keyword].to_vec();
+    lifted.push(TokenTree::Ident(new_name.clone()));
+    lifted.extend(item[keyword + 2..params].iter().cloned());
+
+    let mut self_param: Vec<TokenTree> = param_tokens[..self_index].to_vec();
+    self_param.push(TokenTree::Ident(Ident::new("mut", param_tokens[self_index].span())));
+    self_param.extend(param_tokens[self_index..].iter().cloned());
+    let mut params_lifted = Group::new(Delimiter::Parenthesis, self_param.into_iter().collect());
+    params_lifted.set_span(params_group.span());
+    lifted.push(TokenTree::Group(params_lifted));
+
+    lifted.extend(item[params + 1..ret_start].iter().cloned());
+    lifted.extend(make_mutable(ret));
+    lifted.extend(item[ret_end..body_index].iter().cloned());
+    let mut body_lifted = Group::new(Delimiter::Brace, lift_body(body.stream(), renames)?);
+    body_lifted.set_span(body.span());
+    lifted.push(TokenTree::Group(body_lifted));
+    Ok(Some((lifted, new_name)))
+}
+
+/// Whether the parameters start with `&self` or `&'a self`, and if so,
+/// the lifetime of `self` and where the `self` is.
+fn shared_self(params: &[TokenTree]) -> Option<(Option<String>, usize)> {
+    if !is_punct(params.first()?, '&') {
+        return None;
+    }
+    let (lifetime, next) = lifetime_after(params, 0);
+    if is_ident(params.get(next)?, "self") {
+        Some((lifetime, next))
+    } else {
+        None
+    }
+}
+
+/// Whether a return type has shared references to lift. References that
+/// are mutable or `'static` already are left alone, while a reference
+/// that doesn't borrow from `self`, or that refers to another reference,
+/// can't be lifted, since it's not clear what it should turn into.
+fn has_reference(tokens: &[TokenTree], name: &Ident, self_lifetime: Option<&str>) -> Result<bool, TokenStream> {
+    let mut found = false;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Punct(amp) if amp.as_char() == '&' => {
+                let (lifetime, next) = lifetime_after(tokens, i);
+                if tokens.get(next).is_some_and(|token| is_ident(token, "mut")) {
+                    continue;
+                }
+                match lifetime.as_deref() {
+                    Some("'static") => continue,
+                    None | Some("'_") => {}
+                    Some(lifetime) if Some(lifetime) == self_lifetime => {}
+                    Some(lifetime) => {
+                        return Err(error(
+                            tokens[i + 1].span(),
+                            &format!(
+                                "`{name}` can't be lifted, since it returns a reference that lives for `{lifetime}`, \
+                                 which a mutable borrow of `self` can't give out"
+                            ),
+                        ));
+                    }
+                }
+                if contains_reference(&tokens[next..referent_end(tokens, next)]) {
+                    return Err(error(
+                        amp.span(),
+                        &format!(
+                            "`{name}` can't be lifted, since it returns a reference to a reference, \
+                             and it's not clear which of them should be mutable"
+                        ),
+                    ));
+                }
+                found = true;
+            }
+            TokenTree::Group(group) => {
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                found |= has_reference(&inner, name, self_lifetime)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(found)
+}
+
+/// Finds the end of the type that starts at `start`, which is a group,
+/// another reference, or a path with generic arguments.
+fn referent_end(tokens: &[TokenTree], start: usize) -> usize {
+    match tokens.get(start) {
+        Some(TokenTree::Group(_)) => start + 1,
+        Some(token) if is_punct(token, '&') => tokens.len(),
+        _ => {
+            let mut i = start;
+            while let Some(token) = tokens.get(i) {
+                if is_punct(token, '<') {
+                    i = angle_end(tokens, i).map_or(tokens.len(), |end| end + 1);
+                } else if matches!(token, TokenTree::Ident(_)) || is_punct(token, ':') || is_punct(token, '\'') {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            i
+        }
+    }
+}
+
+fn contains_reference(tokens: &[TokenTree]) -> bool {
+    tokens.iter().any(|token| match token {
+        TokenTree::Group(group) => contains_reference(&group.stream().into_iter().collect::<Vec<_>>()),
+        token => is_punct(token, '&'),
+    })
+}
+
+/// The lifetime of the reference at `i`, if it has one, and where the
+/// rest of the reference starts.
+fn lifetime_after(tokens: &[TokenTree], i: usize) -> (Option<String>, usize) {
+    match (tokens.get(i + 1), tokens.get(i + 2)) {
+        (Some(TokenTree::Punct(quote)), Some(TokenTree::Ident(lifetime))) if quote.as_char() == '\'' => {
+            (Some(format!("'{lifetime}")), i + 3)
+        }
+        _ => (None, i + 1),
+    }
+}
+
+/// Adds `mut` to the shared references of a return type, other than the
+/// `'static` ones.
+fn make_mutable(tokens: &[TokenTree]) -> Vec<TokenTree> {
+    let mut output = Vec::with_capacity(tokens.len() + 1);
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                let mut lifted = Group::new(group.delimiter(), make_mutable(&inner).into_iter().collect());
+                lifted.set_span(group.span());
+                output.push(TokenTree::Group(lifted));
+                i += 1;
+            }
+            token if is_punct(token, '&') => {
+                let (lifetime, next) = lifetime_after(tokens, i);
+                output.extend(tokens[i..next].iter().cloned());
+                let mutable = tokens.get(next).is_some_and(|token| is_ident(token, "mut"));
+                if !mutable && lifetime.as_deref() != Some("'static") {
+                    output.push(TokenTree::Ident(Ident::new("mut", token.span())));
+                }
+                i = next;
+            }
+            token => {
+                output.push(token.clone());
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+/// Borrows `self` mutably in a body, and renames the methods it calls on
+/// `self` with the rename table.
+fn lift_body(body: TokenStream, renames: &[(String, String)]) -> Result<TokenStream, TokenStream> {
+    let tokens: Vec<TokenTree> = body.into_iter().collect();
+    let mut output = Vec::with_capacity(tokens.len());
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => {
+                let mut lifted = Group::new(group.delimiter(), lift_body(group.stream(), renames)?);
+                lifted.set_span(group.span());
+                output.push(TokenTree::Group(lifted));
+            }
+            TokenTree::Ident(method) if i > 0 && is_punct(&tokens[i - 1], '.') && is_call(&tokens, i + 1) => {
+                match renames.iter().find(|(shared, _)| *shared == method.to_string()) {
+                    Some((_, mutable)) => {
+                        let receiver = &tokens[receiver_start(&tokens, i - 1)..i - 1];
+                        if receiver.first().is_some_and(|token| is_ident(token, "self")) {
+                            output.push(TokenTree::Ident(Ident::new(mutable, method.span())));
+                        } else if mentions_self(receiver) {
+                            return Err(error(
+                                method.span(),
+                                &format!(
+                                    "cannot lift `{method}`, since it's not clear whether it's called on \
+                                     something borrowed from `self`, which would need `{mutable}`"
+                                ),
+                            ));
+                        } else {
+                            output.push(token.clone());
+                        }
+                    }
+                    None => output.push(token.clone()),
+                }
+            }
+            TokenTree::Ident(this) if this.to_string() == "self" && borrows(&tokens, i) => {
+                output.push(TokenTree::Ident(Ident::new("mut", this.span())));
+                output.push(token.clone());
+            }
+            token => output.push(token.clone()),
+        }
+    }
+    Ok(output.into_iter().collect())
+}
+
+/// Finds where the receiver of the method call at the `.` at `dot`
+/// starts, going back over fields, calls, indexing, `?` and paths.
+fn receiver_start(tokens: &[TokenTree], dot: usize) -> usize {
+    let mut start = dot;
+    while start > 0 {
+        let token = &tokens[start - 1];
+        let part = match token {
+            TokenTree::Group(group) => group.delimiter() != Delimiter::Brace,
+            TokenTree::Punct(punct) if punct.as_char() == ':' => {
+                // The colons of a path, and not the one of a field.
+                punct.spacing() == Spacing::Joint || start > 1 && is_punct(&tokens[start - 2], ':')
+            }
+            TokenTree::Punct(punct) => ['.', '?'].contains(&punct.as_char()),
+            // Two names in a row, like `return self`, aren't one path.
+            TokenTree::Ident(_) => !tokens.get(start).is_some_and(|next| matches!(next, TokenTree::Ident(_))),
+            TokenTree::Literal(_) => true,
+        };
+        if !part {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+fn mentions_self(tokens: &[TokenTree]) -> bool {
+    tokens.iter().any(|token| match token {
+        TokenTree::Group(group) => mentions_self(&group.stream().into_iter().collect::<Vec<_>>()),
+        token => is_ident(token, "self"),
+    })
+}
+
+/// Whether the token at `i` is borrowed by the `&` right before it, and
+/// isn't the right hand side of a `&` or `&&`.
+fn borrows(tokens: &[TokenTree], i: usize) -> bool {
+    if i == 0 || !is_punct(&tokens[i - 1], '&') {
+        return false;
+    }
+    match i.checked_sub(2).map(|before| &tokens[before]) {
+        None => true,
+        Some(TokenTree::Punct(punct)) => !(punct.as_char() == '&' && punct.spacing() == Spacing::Joint),
+        Some(TokenTree::Ident(ident)) => {
+            ["return", "in", "break", "match", "if", "while", "else"].contains(&ident.to_string().as_str())
+        }
+        Some(TokenTree::Literal(_)) => false,
+        Some(TokenTree::Group(group)) => group.delimiter() == Delimiter::Brace,
+    }
+}
+
+/// Whether the tokens at `i` are the arguments of a method call, either
+/// right away or after a `::<...>`.
+fn is_call(tokens: &[TokenTree], i: usize) -> bool {
+    match tokens.get(i) {
+        Some(TokenTree::Group(group)) => group.delimiter() == Delimiter::Parenthesis,
+        Some(TokenTree::Punct(colon)) => colon.as_char() == ':' && colon.spacing() == Spacing::Joint,
+        _ => false,
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error;
+use proc_macro::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+
+/// Replaces every `#concat(...)` in the brackets at the start of the
+/// input with a single identifier, made by pasting together the
+/// identifiers and literals in the parentheses. A string literal is
+/// pasted without its quotes. The tokens after the brackets are the body
+/// of a function or an impl, whose statements and items are filled in and
+/// pasted by macros of their own, so they are left as they are.
+pub(crate) fn paste(input: TokenStream) -> Result<TokenStream, TokenStream> {
+    let mut tokens = input.into_iter();
+    let mut output = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => replace(group.stream())?,
+        other => {
+            let span = other.map_or_else(Span::call_site, |token| token.span());
+            return Err(error(span, "expected the tokens to paste in `[...]`"));
+        }
+    };
+    output.extend(tokens);
+    Ok(output)
+}
+
+fn replace(input: TokenStream) -> Result<TokenStream, TokenStream> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        match (&tokens[i], tokens.get(i + 1), tokens.get(i + 2)) {
+            (
+                TokenTree::Punct(hash),
+                Some(TokenTree::Ident(keyword)),
+                Some(TokenTree::Group(parts)),
+            ) if hash.as_char() == '#'
+                && keyword.to_string() == "concat"
+                && parts.delimiter() == Delimiter::Parenthesis =>
+            {
+                output.push(TokenTree::Ident(pasted(keyword.span(), parts.stream())?));
+                i += 3;
+            }
+            (TokenTree::Group(group), _, _) => {
+                let mut pasted = Group::new(group.delimiter(), replace(group.stream())?);
+                pasted.set_span(group.span());
+                output.push(TokenTree::Group(pasted));
+                i += 1;
+            }
+            (token, _, _) => {
+                output.push(token.clone());
+                i += 1;
+            }
+        }
+    }
+    Ok(output.into_iter().collect())
+}
+
+/// Pastes the parts of a `#concat(...)` together. The identifier gets the
+/// span of the first part, so that it resolves like the code it came from.
+fn pasted(keyword: Span, parts: TokenStream) -> Result<Ident, TokenStream> {
+    let mut name = String::new();
+    let mut span = None;
+    push_parts(&mut name, &mut span, parts)?;
+
+    let span = span.unwrap_or(keyword);
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(first) => {
+            (first.is_ascii_alphabetic() || first == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && name != "_"
+        }
+        None => false,
+    };
+    if valid {
+        Ok(Ident::new(&name, span))
+    } else {
+        Err(error(
+            span,
+            &format!("`#concat(...)` made `{name}`, which isn't an identifier"),
+        ))
+    }
+}
+
+fn push_parts(
+    name: &mut String,
+    span: &mut Option<Span>,
+    parts: TokenStream,
+) -> Result<(), TokenStream> {
+    for part in parts {
+        match part {
+            TokenTree::Ident(ident) => {
+                let text = ident.to_string();
+                span.get_or_insert(ident.span());
+                name.push_str(text.strip_prefix("r#").unwrap_or(&text));
+            }
+            TokenTree::Literal(literal) => {
+                let text = literal.to_string();
+                span.get_or_insert(literal.span());
+                match text.strip_prefix('"').and_then(|text| text.strip_suffix('"')) {
+                    Some(text) => name.push_str(text),
+                    None => name.push_str(&text),
+                }
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+                push_parts(name, span, group.stream())?;
+            }
+            other => {
+                return Err(error(
+                    other.span(),
+                    "`#concat(...)` can only paste identifiers and literals",
+                ))
+            }
+        }
+    }
+    Ok(())
+}
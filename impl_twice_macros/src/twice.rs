@@ -0,0 +1,228 @@
+use crate::{error, is_arrow, is_ident, is_punct};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Emits the struct it's put on as it is, along with a copy named after
+/// `mut = Name` where the fields that are shared references are mutable
+/// references instead. Derives of `Clone` and `Copy` are left off of the
+/// copy, since mutable references are neither, and so are doc comments,
+/// which describe the shared struct.
+pub(crate) fn twice(args: TokenStream, item: TokenStream) -> Result<TokenStream, TokenStream> {
+    let name = mut_name(args)?;
+    let tokens: Vec<TokenTree> = item.clone().into_iter().collect();
+
+    let keyword = tokens
+        .iter()
+        .position(|token| is_ident(token, "struct"))
+        .ok_or_else(|| {
+            let span = tokens.first().map_or_else(Span::call_site, TokenTree::span);
+            error(span, "`#[twice(mut = ...)]` only goes on structs")
+        })?;
+    let shared = match tokens.get(keyword + 1) {
+        Some(TokenTree::Ident(shared)) => shared.clone(),
+        _ => return Err(error(tokens[keyword].span(), "expected the name of the struct")),
+    };
+
+    let mut copy = Vec::new();
+    let mut i = 0;
+    while i < keyword {
+        match (&tokens[i], tokens.get(i + 1)) {
+            (TokenTree::Punct(hash), Some(TokenTree::Group(attr))) if hash.as_char() == '#' => {
+                if let Some(attr) = copied_attr(attr) {
+                    copy.push(tokens[i].clone());
+                    copy.push(TokenTree::Group(attr));
+                }
+                i += 2;
+            }
+            (token, _) => {
+                copy.push(token.clone());
+                i += 1;
+            }
+        }
+    }
+    let doc = format!(" The mutable counterpart of [`{shared}`].");
+    copy.splice(
+        0..0,
+        vec![
+            TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+            TokenTree::Group(Group::new(
+                Delimiter::Bracket,
+                vec![
+                    TokenTree::Ident(Ident::new("doc", Span::call_site())),
+                    TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+                    TokenTree::Literal(Literal::string(&doc)),
+                ]
+                .into_iter()
+                .collect(),
+            )),
+        ],
+    );
+    copy.push(tokens[keyword].clone());
+    copy.push(TokenTree::Ident(name));
+    let fields = fields(&tokens, keyword + 2);
+    for (i, token) in tokens.iter().enumerate().skip(keyword + 2) {
+        match token {
+            TokenTree::Group(group) if Some(i) == fields => {
+                copy.push(lift_fields(group, group.delimiter() == Delimiter::Brace)?);
+            }
+            token => copy.push(token.clone()),
+        }
+    }
+
+    let mut output = item;
+    output.extend(copy);
+    Ok(output)
+}
+
+/// Finds the fields of the struct. Named fields are always last, while
+/// tuple fields come right after the generics, before any where clause.
+fn fields(tokens: &[TokenTree], start: usize) -> Option<usize> {
+    if let Some(TokenTree::Group(group)) = tokens.last() {
+        if group.delimiter() == Delimiter::Brace {
+            return Some(tokens.len() - 1);
+        }
+    }
+    let mut depth = 0_usize;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            TokenTree::Group(group) if depth == 0 && group.delimiter() == Delimiter::Parenthesis => {
+                return Some(i);
+            }
+            token if is_punct(token, '<') => depth += 1,
+            token if is_punct(token, '>') && !is_arrow(tokens, i) => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads the `mut = Name` of the attribute.
+fn mut_name(args: TokenStream) -> Result<Ident, TokenStream> {
+    let args: Vec<TokenTree> = args.into_iter().collect();
+    match args.as_slice() {
+        [TokenTree::Ident(keyword), TokenTree::Punct(eq), TokenTree::Ident(name)]
+            if keyword.to_string() == "mut" && eq.as_char() == '=' =>
+        {
+            Ok(name.clone())
+        }
+        _ => {
+            let span = args.first().map_or_else(Span::call_site, TokenTree::span);
+            Err(error(span, "expected `mut = Name`, with the name of the mutable struct"))
+        }
+    }
+}
+
+/// The attribute as it goes on the mutable struct, if at all.
+fn copied_attr(attr: &Group) -> Option<Group> {
+    let tokens: Vec<TokenTree> = attr.stream().into_iter().collect();
+    match tokens.as_slice() {
+        [TokenTree::Ident(doc), ..] if doc.to_string() == "doc" => None,
+        [TokenTree::Ident(derive), TokenTree::Group(derives)] if derive.to_string() == "derive" => {
+            let derives: Vec<TokenTree> = derives.stream().into_iter().collect();
+            let kept: Vec<TokenTree> = derives
+                .split(|token| is_punct(token, ','))
+                .filter(|path| match path.last() {
+                    Some(TokenTree::Ident(name)) => name.to_string() != "Clone" && name.to_string() != "Copy",
+                    _ => true,
+                })
+                .filter(|path| !path.is_empty())
+                .flat_map(|path| {
+                    let mut path = path.to_vec();
+                    path.push(TokenTree::Punct(Punct::new(',', Spacing::Alone)));
+                    path
+                })
+                .collect();
+            if kept.is_empty() {
+                return None;
+            }
+            let mut kept = Group::new(Delimiter::Parenthesis, kept.into_iter().collect());
+            kept.set_span(tokens[1].span());
+            let mut attr_copy = Group::new(
+                Delimiter::Bracket,
+                vec![tokens[0].clone(), TokenTree::Group(kept)].into_iter().collect(),
+            );
+            attr_copy.set_span(attr.span());
+            Some(attr_copy)
+        }
+        _ => Some(attr.clone()),
+    }
+}
+
+/// Turns the shared references of the fields into mutable ones. A field
+/// that starts with `&&` is an error, since the outer reference, which is
+/// the one made mutable, can't have a lifetime.
+fn lift_fields(fields: &Group, named: bool) -> Result<TokenTree, TokenStream> {
+    let tokens: Vec<TokenTree> = fields.stream().into_iter().collect();
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut depth = 0_usize;
+    let mut field_start = true;
+    let mut i = 0;
+    while i < tokens.len() {
+        if field_start {
+            let start = type_start(&tokens[i..], named) + i;
+            output.extend(tokens[i..start].iter().cloned());
+            i = start;
+            field_start = false;
+            if let Some(TokenTree::Punct(and)) = tokens.get(i).filter(|token| is_punct(token, '&')) {
+                if and.spacing() == Spacing::Joint && tokens.get(i + 1).is_some_and(|token| is_punct(token, '&')) {
+                    return Err(error(
+                        and.span(),
+                        "expected a lifetime on the outer reference, like `&'a &'b T`, since it's the one made mutable",
+                    ));
+                }
+                // The lifetime is a `'` joined to an identifier.
+                let end = if tokens.get(i + 1).is_some_and(|token| is_punct(token, '\'')) {
+                    i + 3
+                } else {
+                    i + 1
+                };
+                output.extend(tokens[i..end.min(tokens.len())].iter().cloned());
+                if !tokens.get(end).is_some_and(|token| is_ident(token, "mut")) {
+                    output.push(TokenTree::Ident(Ident::new("mut", tokens[i].span())));
+                }
+                i = end;
+            }
+            continue;
+        }
+        let token = &tokens[i];
+        if is_punct(token, '<') {
+            depth += 1;
+        } else if is_punct(token, '>') && !is_arrow(&tokens, i) {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && is_punct(token, ',') {
+            field_start = true;
+        }
+        output.push(token.clone());
+        i += 1;
+    }
+    let mut lifted = Group::new(fields.delimiter(), output.into_iter().collect());
+    lifted.set_span(fields.span());
+    Ok(TokenTree::Group(lifted))
+}
+
+/// Finds where the type of a field starts, after its attributes,
+/// visibility and name.
+fn type_start(tokens: &[TokenTree], named: bool) -> usize {
+    let mut i = 0;
+    while tokens.get(i).is_some_and(|token| is_punct(token, '#')) {
+        i += 2;
+    }
+    if tokens.get(i).is_some_and(|token| is_ident(token, "pub")) {
+        i += 1;
+        // `pub(crate)` and the like, but not `pub (u8, u8)`.
+        if let Some(TokenTree::Group(group)) = tokens.get(i) {
+            let restriction = group.stream().into_iter().next();
+            if group.delimiter() == Delimiter::Parenthesis
+                && restriction.is_some_and(|token| {
+                    ["crate", "self", "super", "in"].iter().any(|name| is_ident(&token, name))
+                })
+            {
+                i += 1;
+            }
+        }
+    }
+    if named {
+        // The name and the `:`.
+        i += 2;
+    }
+    i.min(tokens.len())
+}
@@ -64,18 +64,22 @@
 //!         pub fn get(&self, index: usize) -> Option<&'_ T> {
 //!             self.0.get(index)
 //!         }
-//!     }
-//! );
 //!
-//! impl<T> WrappedSliceMut<'_, T> {
-//!     pub fn get_mut(&mut self, index: usize) -> Option<&'_ mut T> {
-//!         self.0.get_mut(index)
+//!         #[only(WrappedSliceMut)]
+//!         pub fn get_mut(&mut self, index: usize) -> Option<&'_ mut T> {
+//!             self.0.get_mut(index)
+//!         }
 //!     }
-//! }
+//! );
 //! ```
 //!
 //! As you can see, the two methods ``inner`` and ``get`` that were duplicated
-//! are now only implemented once.
+//! are now only implemented once. ``get_mut`` is marked with ``#[only(...)]``,
+//! so it is only implemented on ``WrappedSliceMut``. There is also
+//! ``#[except(...)]``, for items that go on every type except the ones listed.
+//! A type is referred to by the last identifier of its path, so
+//! ``crate::views::Slice<'_, T>`` is ``Slice``, and in trait impls the
+//! trait's name works too. Both also work on the statements of a method.
 //!
 //! # Usage
 //! There are quite a few different ways to use the macro based on what you want.
@@ -125,9 +129,10 @@
 //!     }
 //! );
 //! ```
-//! Adding type bounds is done with 'where'.
-//! Here this library differs from normal impls in that you have
-//! to add parenthesees after the where.
+//! Adding type bounds is done with 'where', just like in a normal impl.
+//! The where clause can also be wrapped in parentheses, like
+//! `where (T: Clone)`, which is what earlier versions of this crate
+//! required.
 //! ```
 //! # use impl_twice::impl_twice;
 //! # #[allow(unused)]
@@ -140,7 +145,9 @@
 //!     impl<T>
 //!         Borrowed<'_, T>,
 //!         BorrowedMut<'_, T>
-//!     where (T: Clone) {
+//!     where
+//!         T: Clone,
+//!     {
 //!         fn to_owned(&self) -> Owned<T> {
 //!             Owned(self.0.clone())
 //!         }
@@ -162,7 +169,7 @@
 //!         Debug for Borrowed<'_, T>,
 //!         Debug for BorrowedMut<'_, T>,
 //!         Debug for Owned<T>
-//!     where (T: Debug) {
+//!     where T: Debug {
 //!         fn fmt(&self, f: &mut Formatter<'_>) -> Result {
 //!             write!(f, "[{:?}]", self.0)
 //!         }
@@ -187,7 +194,7 @@
 //! struct Simple<T>(T);
 //!
 //! impl_twice!(
-//!     impl<A, B> Complex<A, B> where (A: Clone, B: Clone)
+//!     impl<A, B> Complex<A, B> where A: Clone, B: Clone
 //!     impl<T> Simple<T> where (T: Clone) {
 //!         fn redundant_clone_method_for_example_purposes(&self) -> Self {
 //!             self.clone()
@@ -196,76 +203,3796 @@
 //! );
 //! ```
 //!
-//! # Limitations
-//! * Trait, type names and generic parameters are simply tokens. That means, you cannot specify a
-//! path with ``::``, so you have to ``use`` the items first before implementing them. This also
-//! means that the generic parameters cannot depend on other generic parameters. This
-//! might get implemented eventually however.
+//! If the generics are the same and only the bounds differ, a type can
+//! have where predicates of its own instead, in parentheses after it.
+//! They are added to the `where` of the group for that type only. The
+//! `,` after them is optional. After the last type, parentheses without
+//! a `,` are still the `where` of the whole group, like in the example
+//! above, unless another type of the group has predicates of its own.
+//! ```
+//! # use impl_twice::impl_twice;
+//! struct Copied<T>(T);
+//! struct Cloned<T>(T);
 //!
-
-/// A macro for avoiding code duplication for immutable and mutable types.
-/// Check out the crate level documentation for more information
-#[macro_export]
-macro_rules! impl_twice {
-    () => {};
-    (impl $(<$($gen_args:tt),*>)? $(where ($($where_args:tt)*))? { $($content:item)* }$($extra:tt)*) => {
-        impl_twice!($($extra)*);
-    };
-    ({ $($content:item)* }$($extra:tt)*) => {
-        impl_twice!($($extra)*);
-    };
-    (
-        impl $(<$($gen_args:tt),*>)?
-            $name:ident$(<$($name_param:tt),*>)?
-            $(for $ename:ident$(<$($ename_param:tt),*>)?)?
-            $(,
-                $more_name:ident$(<$($more_name_param:tt),*>)?
-                $(for $more_ename:ident$(<$($more_ename_param:tt),*>)?)?
-            )*
-        $(where ($($where_args:tt)*))?
-        $(
-            impl $(<$($gen_args2:tt),*>)?
-                $name2:ident$(<$($name_param2:tt),*>)?
-                $(for $ename2:ident$(<$($ename_param2:tt),*>)?)?
-                $(,
-                    $more_name2:ident$(<$($more_name_param2:tt),*>)?
-                    $(for $more_ename2:ident$(<$($more_ename_param2:tt),*>)?)?
-                )*
-            $(where ($($where_args2:tt)*))?
-        )*
-        {
-            $($content:item)*
-        }
-        $($extra:tt)*
-    ) => {
-        impl$(<$($gen_args),*>)? $name $(<$($name_param),*>)? $(for $ename$(<$($ename_param),*>)?)? $(where $($where_args)*)? {
-            $($content)*
-        }
-        impl_twice!(
-            impl $(<$($gen_args),*>)? $(
-                $more_name$(<$($more_name_param),*>)?
-                $(for $more_ename$(<$($more_ename_param),*>)?)?
-            ),*
-            $(where ($($where_args)*))?
-            {
-                $($content)*
-            }
-        );
-        impl_twice!(
-            $(
-                impl $(<$($gen_args2),*>)?
-                    $name2$(<$($name_param2),*>)?
-                    $(for $ename2$(<$($ename_param2),*>)?)?
-                    $(,
-                        $more_name2$(<$($more_name_param2),*>)?
-                        $(for $more_ename2$(<$($more_ename_param2),*>)?)?
-                    )*
-                $(where ($($where_args2)*))?
-            )*
-            {
-                $($content)*
-            }
-        );
-        impl_twice!($($extra)*);
+//! impl_twice!(
+//!     impl<T> Copied<T> where (T: Copy), Cloned<T> where (T: Clone) where T: PartialEq {
+//!         fn is(&self, other: &T) -> bool {
+//!             self.0 == *other
+//!         }
+//!
+//!         #[only(Copied)]
+//!         fn get(&self) -> T {
+//!             self.0
+//!         }
+//!
+//!         #[only(Cloned)]
+//!         fn get(&self) -> T {
+//!             self.0.clone()
+//!         }
+//!     }
+//! );
+//!
+//!
+//! impl_twice!(
+//!     impl<T> Copied<T> where (T: Copy + Default), Cloned<T> where (T: Clone + Default) {
+//!         fn new() -> Self {
+//!             Self(T::default())
+//!         }
+//!     }
+//! );
+//!
+//! assert!(Cloned(String::from("a")).is(&"a".into()));
+//! assert_eq!(Copied(2).get(), 2);
+//! assert_eq!(Cloned::<String>::new().get(), "");
+//! ```
+//!
+//! Trait and type names can be full paths, so you don't have to ``use``
+//! them first. Leading ``::``, ``crate::``, ``super::`` and qualified
+//! types like ``<T as Trait>::Assoc`` all work.
+//! ```
+//! # use impl_twice::impl_twice;
+//! mod views {
+//!     pub struct Slice<'a, T>(pub &'a [T]);
+//!     pub struct SliceMut<'a, T>(pub &'a mut [T]);
+//! }
+//!
+//! impl_twice!(
+//!     impl<T>
+//!         core::ops::Index<usize> for crate::views::Slice<'_, T>,
+//!         ::core::ops::Index<usize> for views::SliceMut<'_, T>
+//!     {
+//!         type Output = T;
+//!
+//!         fn index(&self, index: usize) -> &T {
+//!             &self.0[index]
+//!         }
+//!     }
+//! );
+//!
+//! pub struct Marker;
+//!
+//! pub trait Container {
+//!     type Item;
+//! }
+//!
+//! impl Container for () {
+//!     type Item = Marker;
+//! }
+//!
+//! impl_twice!(
+//!     impl Default for <() as self::Container>::Item {
+//!         fn default() -> Self {
+//!             Marker
+//!         }
+//!     }
+//! );
+//! # fn main() {}
+//! ```
+//!
+//! The types don't have to be named types either. References, slices,
+//! arrays, tuples, function pointers and trait objects all work, which is
+//! handy for implementing something on both `&T` and `&mut T`.
+//! ```
+//! # use impl_twice::impl_twice;
+//! trait Describe {
+//!     fn describe(&self) -> &'static str {
+//!         "something"
+//!     }
+//! }
+//!
+//! impl_twice!(
+//!     impl<'a, T> Describe for &'a T, Describe for &'a mut T {}
+//!     impl<T, const N: usize> Describe for [T; N] {}
+//!     impl<T> Describe for [T], Describe for (T, T) {}
+//!     impl<T, U> Describe for fn(T) -> U, Describe for for<'a> fn(&'a T) -> U {}
+//!     impl Describe for dyn core::any::Any, Describe for dyn core::any::Any + Send {}
+//! );
+//!
+//! assert_eq!((&mut 5).describe(), "something");
+//! assert_eq!([1, 2, 3][..].describe(), "something");
+//! ```
+//!
+//! Generic arguments can be nested as deep as you like, both on the
+//! traits and on the types.
+//! ```
+//! # use impl_twice::impl_twice;
+//! # #[allow(unused)]
+//! struct Holder<T>(T);
+//! # #[allow(unused)]
+//! struct HolderMut<'a, T>(&'a mut T);
+//!
+//! impl_twice!(
+//!     impl<T>
+//!         PartialEq<Option<Vec<T>>> for Holder<Vec<T>>,
+//!         PartialEq<Option<Vec<T>>> for HolderMut<'_, Vec<T>>
+//!     where (T: PartialEq) {
+//!         fn eq(&self, other: &Option<Vec<T>>) -> bool {
+//!             other.as_ref() == Some(&self.0)
+//!         }
+//!     }
+//!
+//!     impl<T> AsRef<Box<[T]>> for Holder<Box<[T]>> {
+//!         fn as_ref(&self) -> &Box<[T]> {
+//!             &self.0
+//!         }
+//!     }
+//! );
+//!
+//! let holder = Holder(vec![1, 2]);
+//! assert!(holder == Some(vec![1, 2]));
+//! ```
+//!
+//! Traits and types that were already parsed by another macro, as `ty` or
+//! `path` fragments, can be passed on as they are. This makes it easy to
+//! wrap `impl_twice!` in your own `macro_rules!` helpers.
+//! ```
+//! # use impl_twice::impl_twice;
+//! # #[allow(unused)]
+//! # struct Borrowed<'a, T>(&'a T);
+//! # #[allow(unused)]
+//! # struct BorrowedMut<'a, T>(&'a mut T);
+//! trait Peek<T> {
+//!     fn peek(&self) -> &T;
+//! }
+//!
+//! macro_rules! impl_peek {
+//!     ($trait_:path => $($ty:ty),*) => {
+//!         impl_twice!(
+//!             impl<'a, T> $($trait_ for $ty),* {
+//!                 fn peek(&self) -> &T {
+//!                     &*self.0
+//!                 }
+//!             }
+//!         );
+//!     };
+//! }
+//!
+//! impl_peek!(Peek<T> => Borrowed<'a, T>, BorrowedMut<'a, T>);
+//!
+//! assert_eq!(*Borrowed(&3).peek(), 3);
+//! ```
+//!
+//! A fragment can't be looked into, so `#type_name` is found in its text
+//! instead, and still is `"BorrowedMut"` for `BorrowedMut<'a, T>`. That
+//! doesn't work for `#[only(...)]` and `#[except(...)]`, which need the
+//! name as an identifier, so they can't name a type passed on this way.
+//! Tagging the type with `mut` in the helper works, since `mut` is a name
+//! of its own.
+//! ```
+//! # use impl_twice::impl_twice;
+//! # #[allow(unused)]
+//! # struct Borrowed<'a, T>(&'a T);
+//! # #[allow(unused)]
+//! # struct BorrowedMut<'a, T>(&'a mut T);
+//! macro_rules! impl_name {
+//!     ($ty:ty, mut $mut_ty:ty) => {
+//!         impl_twice!(
+//!             impl<'a, T> $ty, mut $mut_ty {
+//!                 const NAME: &'static str = #type_name;
+//!
+//!                 #[only(mut)]
+//!                 fn is_mut(&self) -> bool {
+//!                     true
+//!                 }
+//!             }
+//!         );
+//!     };
+//! }
+//!
+//! impl_name!(Borrowed<'a, T>, mut BorrowedMut<'a, T>);
+//!
+//! assert_eq!(Borrowed::<u8>::NAME, "Borrowed");
+//! assert_eq!(BorrowedMut::<u8>::NAME, "BorrowedMut");
+//! assert!(BorrowedMut(&mut 1).is_mut());
+//! ```
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! # struct Borrowed<'a, T>(&'a T);
+//! # struct BorrowedMut<'a, T>(&'a mut T);
+//! macro_rules! impl_name {
+//!     ($($ty:ty),*) => {
+//!         impl_twice!(
+//!             impl<'a, T> $($ty),* {
+//!                 #[only(BorrowedMut)]
+//!                 fn is_mut(&self) -> bool {
+//!                     true
+//!                 }
+//!             }
+//!         );
+//!     };
+//! }
+//!
+//! impl_name!(Borrowed<'a, T>, BorrowedMut<'a, T>);
+//! ```
+//!
+//! The macro doesn't have to be imported by name, it also works through a
+//! full path or under another name.
+//! ```
+//! # #[allow(unused)]
+//! # struct Borrowed<'a, T>(&'a T);
+//! # #[allow(unused)]
+//! # struct BorrowedMut<'a, T>(&'a mut T);
+//! use impl_twice::impl_twice as twice;
+//!
+//! impl_twice::impl_twice!(
+//!     impl<T> Borrowed<'_, T>, BorrowedMut<'_, T> {
+//!         fn get(&self) -> &T {
+//!             self.0
+//!         }
+//!     }
+//! );
+//!
+//! twice!(
+//!     impl<T: Clone> Borrowed<'_, T>, BorrowedMut<'_, T> {
+//!         fn get_cloned(&self) -> T {
+//!             self.0.clone()
+//!         }
+//!     }
+//! );
+//!
+//! assert_eq!(Borrowed(&1).get_cloned(), 1);
+//! ```
+//!
+//! The generic parameters of an `impl` take the same form as in a normal
+//! impl, so bounds, lifetime bounds and const generics can be written
+//! inline instead of in the `where` block.
+//! ```
+//! # use impl_twice::impl_twice;
+//! struct Ring<T, const N: usize>([T; N]);
+//! struct RingMut<'a, T, const N: usize>(&'a mut [T; N]);
+//!
+//! impl_twice!(
+//!     impl<T: Clone, const N: usize> Ring<T, N>
+//!     impl<'a, 'b: 'a, T: Clone + 'b, const N: usize> RingMut<'a, T, N> {
+//!         fn first(&self) -> Option<T> {
+//!             self.0.first().cloned()
+//!         }
+//!     }
+//! );
+//!
+//! let mut array = [1, 2, 3];
+//! assert_eq!(Ring([1, 2, 3]).first(), Some(1));
+//! assert_eq!(RingMut(&mut array).first(), Some(1));
+//! ```
+//!
+//! Attributes before an `impl` go on the impl block of every type after
+//! it, and attributes before a single type only go on the impl block of
+//! that type. This way one of the types can be behind a `#[cfg]` without
+//! splitting up the `impl_twice!`.
+//! ```
+//! # use impl_twice::impl_twice;
+//! struct Slice<'a, T>(&'a [T]);
+//! struct SliceMut<'a, T>(&'a mut [T]);
+//! # #[allow(unused)]
+//! struct BoxedSlice<T>(Box<[T]>);
+//!
+//! impl_twice!(
+//!     #[allow(clippy::len_without_is_empty)]
+//!     impl<T> Slice<'_, T>, SliceMut<'_, T>, #[cfg(feature = "alloc")] BoxedSlice<T> {
+//!         pub fn len(&self) -> usize {
+//!             self.0.len()
+//!         }
+//!     }
+//! );
+//!
+//! assert_eq!(Slice(&[1, 2, 3]).len(), 3);
+//! assert_eq!(SliceMut(&mut [1, 2]).len(), 2);
+//! ```
+//!
+//! Unsafe traits, like `Send` and `Sync`, are implemented with an
+//! `unsafe impl`. It can be mixed with normal `impl`s in the same
+//! `impl_twice!`.
+//! ```
+//! # use impl_twice::impl_twice;
+//! use core::marker::PhantomData;
+//!
+//! struct RawView<'a, T>(*const T, PhantomData<&'a T>);
+//! struct RawViewMut<'a, T>(*mut T, PhantomData<&'a mut T>);
+//!
+//! impl_twice!(
+//!     // Safety: the views act like `&T` and `&mut T`.
+//!     unsafe impl<T: Sync> Send for RawView<'_, T>
+//!     unsafe impl<T: Send> Send for RawViewMut<'_, T> {}
+//!
+//!     impl<T> RawView<'_, T>, RawViewMut<'_, T> {
+//!         pub fn as_ptr(&self) -> *const T {
+//!             self.0
+//!         }
+//!     }
+//! );
+//!
+//! fn assert_send<T: Send>(_: &T) {}
+//! let view = RawView(&1, PhantomData);
+//! assert_send(&view);
+//! assert!(!view.as_ptr().is_null());
+//! ```
+//!
+//! When a single type needs something else than the shared items, it can
+//! list its own items after a `=>`. They replace the shared items with the
+//! same name on that type only, and the other types keep the shared ones.
+//! ```
+//! # use impl_twice::impl_twice;
+//! use core::ops::Index;
+//!
+//! struct Pair<'a, T>(&'a T, &'a T);
+//! struct Repeat<'a, T>(&'a T);
+//!
+//! impl_twice!(
+//!     impl<T> Index<usize> for Pair<'_, T>, Index<usize> for Repeat<'_, T> => {
+//!         fn index(&self, _: usize) -> &T {
+//!             self.0
+//!         }
+//!     } {
+//!         type Output = T;
+//!
+//!         fn index(&self, index: usize) -> &T {
+//!             [self.0, self.1][index]
+//!         }
+//!     }
+//! );
+//!
+//! assert_eq!(Pair(&1, &2)[1], 2);
+//! assert_eq!(Repeat(&1)[1], 1);
+//! ```
+//!
+//! Most of the time the two types only differ in mutability, so the items
+//! are the same except for a `mut` here and there. The mutable types can
+//! be tagged with `mut`, and then the body can use `#mut` where the
+//! tagged types want a `mut` and the other types want nothing, like in
+//! `&#mut self`. A `*#mut T` is `*mut T` on the tagged types and
+//! `*const T` on the others, and `#if_mut(get_mut, get)` picks the first
+//! name on the tagged types and the second on the others, which is handy
+//! for method names. `#[only(mut)]` and `#[except(mut)]` refer to the
+//! tagged types.
+//! ```
+//! # use impl_twice::impl_twice;
+//! struct Slice<'a, T>(&'a [T]);
+//! struct SliceMut<'a, T>(&'a mut [T]);
+//!
+//! impl_twice!(
+//!     impl<T> Slice<'_, T>, mut SliceMut<'_, T> {
+//!         pub fn #if_mut(get_mut, get)(&#mut self, index: usize) -> Option<&#mut T> {
+//!             self.0.#if_mut(get_mut, get)(index)
+//!         }
+//!
+//!         pub fn #if_mut(as_mut_ptr, as_ptr)(&#mut self) -> *#mut T {
+//!             self.0.#if_mut(as_mut_ptr, as_ptr)()
+//!         }
+//!
+//!         #[only(mut)]
+//!         pub fn fill(&mut self, value: T) where T: Clone {
+//!             self.0.fill(value);
+//!         }
+//!     }
+//! );
+//!
+//! let mut array = [1, 2, 3];
+//! let mut slice = SliceMut(&mut array);
+//! slice.fill(0);
+//! *slice.get_mut(1).unwrap() = 2;
+//! assert!(!slice.as_mut_ptr().is_null());
+//! assert_eq!(Slice(&array).get(1), Some(&2));
+//! ```
+//!
+//! The placeholders work on a single type too. An item marked with
+//! `#[twice]` is implemented twice on every type, once as if the type was
+//! tagged with `mut` and once as if it wasn't, so a pair like
+//! `first`/`first_mut` only has to be written once.
+//! ```
+//! # use impl_twice::impl_twice;
+//! struct Stack<T>(Vec<T>);
+//!
+//! impl_twice!(
+//!     impl<T> Stack<T> {
+//!         #[twice]
+//!         pub fn #if_mut(top_mut, top)(&#mut self) -> Option<&#mut T> {
+//!             self.0.#if_mut(last_mut, last)()
+//!         }
+//!
+//!         pub fn push(&mut self, value: T) {
+//!             self.0.push(value);
+//!         }
+//!     }
+//! );
+//!
+//! let mut stack = Stack(Vec::new());
+//! stack.push(1);
+//! *stack.top_mut().unwrap() += 1;
+//! assert_eq!(stack.top(), Some(&2));
+//! ```
+//!
+//! The types can also bring values of their own into the body. Bindings
+//! like `Signed = i8` go first after the `=>` of a type, before any items
+//! it overrides, and the body refers to them as `#Signed`. A binding can
+//! be a type, an expression or an identifier, and every type the body is
+//! implemented on needs all the bindings that the body uses.
+//! ```
+//! # use impl_twice::impl_twice;
+//! trait Bits {
+//!     type Signed;
+//!     const BITS: u32;
+//!
+//!     fn to_signed(self) -> Self::Signed;
+//! }
+//!
+//! impl_twice!(
+//!     impl Bits for u8 => { Signed = i8, BITS = 8 },
+//!          Bits for u16 => { Signed = i16, BITS = 16 } {
+//!         type Signed = #Signed;
+//!         const BITS: u32 = #BITS;
+//!
+//!         fn to_signed(self) -> #Signed {
+//!             self as #Signed
+//!         }
+//!     }
+//! );
+//!
+//! assert_eq!(<u16 as Bits>::BITS, 16);
+//! assert_eq!(200_u8.to_signed(), -56);
+//! ```
+//! Leaving a binding out for one of the types is an error, even if that
+//! type has no bindings at all.
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! # struct Wrapper<T>(T);
+//! # struct Other<T>(T);
+//! impl_twice!(
+//!     impl<T> Wrapper<T> => { Index = u8 }, Other<T> {
+//!         fn first_index(&self) -> #Index {
+//!             0
+//!         }
+//!     }
+//! );
+//! ```
+//!
+//! Every type also has `#type_name`, which is the name of the type as a
+//! string literal, and in trait impls `#trait_name`, which is the name of
+//! the trait. Like with `#[only(...)]`, the name is the last identifier of
+//! the path, so `crate::views::Slice<'_, T>` is `"Slice"`.
+//! ```
+//! # use impl_twice::impl_twice;
+//! use core::fmt;
+//!
+//! struct Slice<'a, T>(&'a [T]);
+//! struct SliceMut<'a, T>(&'a mut [T]);
+//!
+//! impl_twice!(
+//!     impl<T: fmt::Debug> fmt::Debug for Slice<'_, T>, fmt::Debug for SliceMut<'_, T> {
+//!         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!             f.debug_tuple(#type_name).field(&self.0).finish()
+//!         }
+//!     }
+//!
+//!     impl<T> Slice<'_, T>, SliceMut<'_, T> {
+//!         const NAME: &'static str = #type_name;
+//!     }
+//! );
+//!
+//! assert_eq!(format!("{:?}", Slice(&[1, 2])), "Slice([1, 2])");
+//! assert_eq!(SliceMut::<u8>::NAME, "SliceMut");
+//! ```
+//!
+//! With the `paste` feature, `#concat(...)` pastes identifiers and
+//! literals together into a single identifier, after the placeholders
+//! inside it are filled in. That way the names of methods can follow the
+//! type, like `get` and `get_mut`, or `#concat(to_ #type_name)` being
+//! `to_u16` for `u16`. `#if_mut(a)` with only one argument is handy here,
+//! since it's `a` for a type tagged with `mut`, and nothing otherwise. The
+//! values of bindings can use `#concat(...)` too, and `#type_name` and
+//! `#trait_name` in it are the identifiers rather than strings.
+//!
+//! The feature adds a dependency on `impl_twice_macros`, a procedural
+//! macro crate that lives next to this one and has no dependencies of its
+//! own, which is why it's optional. It can't be done without one:
+//! `macro_rules!` can't make new identifiers, and `concat_idents!` is only
+//! there on nightly. Without the feature, the crate has no dependencies.
+#![cfg_attr(feature = "paste", doc = "```")]
+#![cfg_attr(not(feature = "paste"), doc = "```ignore")]
+//! # use impl_twice::impl_twice;
+//! struct Slice<'a, T>(&'a [T]);
+//! struct SliceMut<'a, T>(&'a mut [T]);
+//!
+//! impl_twice!(
+//!     impl<T> Slice<'_, T>, mut SliceMut<'_, T> {
+//!         pub fn #concat(first #if_mut(_mut))(&#mut self) -> Option<&#mut T> {
+//!             self.0.#concat(first #if_mut(_mut))()
+//!         }
+//!     }
+//! );
+//!
+//! let mut array = [1, 2];
+//! let mut slice = SliceMut(&mut array);
+//! *slice.first_mut().unwrap() = 3;
+//! assert_eq!(Slice(&array).first(), Some(&3));
+//! ```
+//! Only the body and the values of bindings can use `#concat(...)`. The
+//! traits and types of an `impl` come before any placeholders are filled
+//! in, so they have to be written out, and `#concat(...)` there is an
+//! error.
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! # struct FooView;
+//! impl_twice!(
+//!     impl #concat(Foo View) {
+//!         fn new() -> Self {
+//!             FooView
+//!         }
+//!     }
+//! );
+//! ```
+//!
+//! A `#name` that is none of these is an error. When any of the types
+//! sharing the body has bindings, the error says which binding is
+//! missing.
+//!
+//! The items of the body, and the statements of every function in it, are
+//! found without walking through them one at a time, so a long body or a
+//! long function doesn't run into the recursion limit.
+//! ```
+//! # use impl_twice::impl_twice;
+//! struct Registers([u32; 200]);
+//! struct RegistersMut<'a>(&'a mut [u32; 200]);
+//!
+//! impl_twice!(
+//!     impl Registers, RegistersMut<'_> {
+//!         pub fn r0(&self) -> u32 { self.0[0] }
+//!         // ...
+//! #         pub fn r1(&self) -> u32 { self.0[1] } pub fn r2(&self) -> u32 { self.0[2] } pub fn r3(&self) -> u32 { self.0[3] } pub fn r4(&self) -> u32 { self.0[4] }
+//! #         pub fn r5(&self) -> u32 { self.0[5] } pub fn r6(&self) -> u32 { self.0[6] } pub fn r7(&self) -> u32 { self.0[7] } pub fn r8(&self) -> u32 { self.0[8] }
+//! #         pub fn r9(&self) -> u32 { self.0[9] } pub fn r10(&self) -> u32 { self.0[10] } pub fn r11(&self) -> u32 { self.0[11] } pub fn r12(&self) -> u32 { self.0[12] }
+//! #         pub fn r13(&self) -> u32 { self.0[13] } pub fn r14(&self) -> u32 { self.0[14] } pub fn r15(&self) -> u32 { self.0[15] } pub fn r16(&self) -> u32 { self.0[16] }
+//! #         pub fn r17(&self) -> u32 { self.0[17] } pub fn r18(&self) -> u32 { self.0[18] } pub fn r19(&self) -> u32 { self.0[19] } pub fn r20(&self) -> u32 { self.0[20] }
+//! #         pub fn r21(&self) -> u32 { self.0[21] } pub fn r22(&self) -> u32 { self.0[22] } pub fn r23(&self) -> u32 { self.0[23] } pub fn r24(&self) -> u32 { self.0[24] }
+//! #         pub fn r25(&self) -> u32 { self.0[25] } pub fn r26(&self) -> u32 { self.0[26] } pub fn r27(&self) -> u32 { self.0[27] } pub fn r28(&self) -> u32 { self.0[28] }
+//! #         pub fn r29(&self) -> u32 { self.0[29] } pub fn r30(&self) -> u32 { self.0[30] } pub fn r31(&self) -> u32 { self.0[31] } pub fn r32(&self) -> u32 { self.0[32] }
+//! #         pub fn r33(&self) -> u32 { self.0[33] } pub fn r34(&self) -> u32 { self.0[34] } pub fn r35(&self) -> u32 { self.0[35] } pub fn r36(&self) -> u32 { self.0[36] }
+//! #         pub fn r37(&self) -> u32 { self.0[37] } pub fn r38(&self) -> u32 { self.0[38] } pub fn r39(&self) -> u32 { self.0[39] } pub fn r40(&self) -> u32 { self.0[40] }
+//! #         pub fn r41(&self) -> u32 { self.0[41] } pub fn r42(&self) -> u32 { self.0[42] } pub fn r43(&self) -> u32 { self.0[43] } pub fn r44(&self) -> u32 { self.0[44] }
+//! #         pub fn r45(&self) -> u32 { self.0[45] } pub fn r46(&self) -> u32 { self.0[46] } pub fn r47(&self) -> u32 { self.0[47] } pub fn r48(&self) -> u32 { self.0[48] }
+//! #         pub fn r49(&self) -> u32 { self.0[49] } pub fn r50(&self) -> u32 { self.0[50] } pub fn r51(&self) -> u32 { self.0[51] } pub fn r52(&self) -> u32 { self.0[52] }
+//! #         pub fn r53(&self) -> u32 { self.0[53] } pub fn r54(&self) -> u32 { self.0[54] } pub fn r55(&self) -> u32 { self.0[55] } pub fn r56(&self) -> u32 { self.0[56] }
+//! #         pub fn r57(&self) -> u32 { self.0[57] } pub fn r58(&self) -> u32 { self.0[58] } pub fn r59(&self) -> u32 { self.0[59] } pub fn r60(&self) -> u32 { self.0[60] }
+//! #         pub fn r61(&self) -> u32 { self.0[61] } pub fn r62(&self) -> u32 { self.0[62] } pub fn r63(&self) -> u32 { self.0[63] } pub fn r64(&self) -> u32 { self.0[64] }
+//! #         pub fn r65(&self) -> u32 { self.0[65] } pub fn r66(&self) -> u32 { self.0[66] } pub fn r67(&self) -> u32 { self.0[67] } pub fn r68(&self) -> u32 { self.0[68] }
+//! #         pub fn r69(&self) -> u32 { self.0[69] } pub fn r70(&self) -> u32 { self.0[70] } pub fn r71(&self) -> u32 { self.0[71] } pub fn r72(&self) -> u32 { self.0[72] }
+//! #         pub fn r73(&self) -> u32 { self.0[73] } pub fn r74(&self) -> u32 { self.0[74] } pub fn r75(&self) -> u32 { self.0[75] } pub fn r76(&self) -> u32 { self.0[76] }
+//! #         pub fn r77(&self) -> u32 { self.0[77] } pub fn r78(&self) -> u32 { self.0[78] } pub fn r79(&self) -> u32 { self.0[79] } pub fn r80(&self) -> u32 { self.0[80] }
+//! #         pub fn r81(&self) -> u32 { self.0[81] } pub fn r82(&self) -> u32 { self.0[82] } pub fn r83(&self) -> u32 { self.0[83] } pub fn r84(&self) -> u32 { self.0[84] }
+//! #         pub fn r85(&self) -> u32 { self.0[85] } pub fn r86(&self) -> u32 { self.0[86] } pub fn r87(&self) -> u32 { self.0[87] } pub fn r88(&self) -> u32 { self.0[88] }
+//! #         pub fn r89(&self) -> u32 { self.0[89] } pub fn r90(&self) -> u32 { self.0[90] } pub fn r91(&self) -> u32 { self.0[91] } pub fn r92(&self) -> u32 { self.0[92] }
+//! #         pub fn r93(&self) -> u32 { self.0[93] } pub fn r94(&self) -> u32 { self.0[94] } pub fn r95(&self) -> u32 { self.0[95] } pub fn r96(&self) -> u32 { self.0[96] }
+//! #         pub fn r97(&self) -> u32 { self.0[97] } pub fn r98(&self) -> u32 { self.0[98] } pub fn r99(&self) -> u32 { self.0[99] } pub fn r100(&self) -> u32 { self.0[100] }
+//! #         pub fn r101(&self) -> u32 { self.0[101] } pub fn r102(&self) -> u32 { self.0[102] } pub fn r103(&self) -> u32 { self.0[103] } pub fn r104(&self) -> u32 { self.0[104] }
+//! #         pub fn r105(&self) -> u32 { self.0[105] } pub fn r106(&self) -> u32 { self.0[106] } pub fn r107(&self) -> u32 { self.0[107] } pub fn r108(&self) -> u32 { self.0[108] }
+//! #         pub fn r109(&self) -> u32 { self.0[109] } pub fn r110(&self) -> u32 { self.0[110] } pub fn r111(&self) -> u32 { self.0[111] } pub fn r112(&self) -> u32 { self.0[112] }
+//! #         pub fn r113(&self) -> u32 { self.0[113] } pub fn r114(&self) -> u32 { self.0[114] } pub fn r115(&self) -> u32 { self.0[115] } pub fn r116(&self) -> u32 { self.0[116] }
+//! #         pub fn r117(&self) -> u32 { self.0[117] } pub fn r118(&self) -> u32 { self.0[118] } pub fn r119(&self) -> u32 { self.0[119] } pub fn r120(&self) -> u32 { self.0[120] }
+//! #         pub fn r121(&self) -> u32 { self.0[121] } pub fn r122(&self) -> u32 { self.0[122] } pub fn r123(&self) -> u32 { self.0[123] } pub fn r124(&self) -> u32 { self.0[124] }
+//! #         pub fn r125(&self) -> u32 { self.0[125] } pub fn r126(&self) -> u32 { self.0[126] } pub fn r127(&self) -> u32 { self.0[127] } pub fn r128(&self) -> u32 { self.0[128] }
+//! #         pub fn r129(&self) -> u32 { self.0[129] } pub fn r130(&self) -> u32 { self.0[130] } pub fn r131(&self) -> u32 { self.0[131] } pub fn r132(&self) -> u32 { self.0[132] }
+//! #         pub fn r133(&self) -> u32 { self.0[133] } pub fn r134(&self) -> u32 { self.0[134] } pub fn r135(&self) -> u32 { self.0[135] } pub fn r136(&self) -> u32 { self.0[136] }
+//! #         pub fn r137(&self) -> u32 { self.0[137] } pub fn r138(&self) -> u32 { self.0[138] } pub fn r139(&self) -> u32 { self.0[139] } pub fn r140(&self) -> u32 { self.0[140] }
+//! #         pub fn r141(&self) -> u32 { self.0[141] } pub fn r142(&self) -> u32 { self.0[142] } pub fn r143(&self) -> u32 { self.0[143] } pub fn r144(&self) -> u32 { self.0[144] }
+//! #         pub fn r145(&self) -> u32 { self.0[145] } pub fn r146(&self) -> u32 { self.0[146] } pub fn r147(&self) -> u32 { self.0[147] } pub fn r148(&self) -> u32 { self.0[148] }
+//! #         pub fn r149(&self) -> u32 { self.0[149] } pub fn r150(&self) -> u32 { self.0[150] } pub fn r151(&self) -> u32 { self.0[151] } pub fn r152(&self) -> u32 { self.0[152] }
+//! #         pub fn r153(&self) -> u32 { self.0[153] } pub fn r154(&self) -> u32 { self.0[154] } pub fn r155(&self) -> u32 { self.0[155] } pub fn r156(&self) -> u32 { self.0[156] }
+//! #         pub fn r157(&self) -> u32 { self.0[157] } pub fn r158(&self) -> u32 { self.0[158] } pub fn r159(&self) -> u32 { self.0[159] } pub fn r160(&self) -> u32 { self.0[160] }
+//! #         pub fn r161(&self) -> u32 { self.0[161] } pub fn r162(&self) -> u32 { self.0[162] } pub fn r163(&self) -> u32 { self.0[163] } pub fn r164(&self) -> u32 { self.0[164] }
+//! #         pub fn r165(&self) -> u32 { self.0[165] } pub fn r166(&self) -> u32 { self.0[166] } pub fn r167(&self) -> u32 { self.0[167] } pub fn r168(&self) -> u32 { self.0[168] }
+//! #         pub fn r169(&self) -> u32 { self.0[169] } pub fn r170(&self) -> u32 { self.0[170] } pub fn r171(&self) -> u32 { self.0[171] } pub fn r172(&self) -> u32 { self.0[172] }
+//! #         pub fn r173(&self) -> u32 { self.0[173] } pub fn r174(&self) -> u32 { self.0[174] } pub fn r175(&self) -> u32 { self.0[175] } pub fn r176(&self) -> u32 { self.0[176] }
+//! #         pub fn r177(&self) -> u32 { self.0[177] } pub fn r178(&self) -> u32 { self.0[178] } pub fn r179(&self) -> u32 { self.0[179] } pub fn r180(&self) -> u32 { self.0[180] }
+//! #         pub fn r181(&self) -> u32 { self.0[181] } pub fn r182(&self) -> u32 { self.0[182] } pub fn r183(&self) -> u32 { self.0[183] } pub fn r184(&self) -> u32 { self.0[184] }
+//! #         pub fn r185(&self) -> u32 { self.0[185] } pub fn r186(&self) -> u32 { self.0[186] } pub fn r187(&self) -> u32 { self.0[187] } pub fn r188(&self) -> u32 { self.0[188] }
+//! #         pub fn r189(&self) -> u32 { self.0[189] } pub fn r190(&self) -> u32 { self.0[190] } pub fn r191(&self) -> u32 { self.0[191] } pub fn r192(&self) -> u32 { self.0[192] }
+//! #         pub fn r193(&self) -> u32 { self.0[193] } pub fn r194(&self) -> u32 { self.0[194] } pub fn r195(&self) -> u32 { self.0[195] } pub fn r196(&self) -> u32 { self.0[196] }
+//! #         pub fn r197(&self) -> u32 { self.0[197] } pub fn r198(&self) -> u32 { self.0[198] }
+//!         pub fn r199(&self) -> u32 { self.0[199] }
+//!     }
+//! );
+//!
+//! let mut values = [0; 200];
+//! values[150] = 7;
+//! assert_eq!(RegistersMut(&mut values).r150(), 7);
+//! assert_eq!(Registers([1; 200]).r199(), 1);
+//! ```
+//! ```
+//! # use impl_twice::impl_twice;
+//! struct Counter;
+//! struct CounterMut<'a>(&'a mut u32);
+//!
+//! impl_twice!(
+//!     impl Counter, CounterMut<'_> {
+//!         fn count(&self) -> u32 {
+//!             let mut count = 0;
+//!             // ...
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//! #         count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1; count += 1;
+//!             count += 1;
+//!             count
+//!         }
+//!     }
+//! );
+//!
+//! assert_eq!(Counter.count(), 300);
+//! assert_eq!(CounterMut(&mut 0).count(), 300);
+//! ```
+//!
+//! # Templates
+//! Impl blocks aren't the only thing that gets duplicated. `for Name in
+//! [...] { ... }` repeats any items, like functions, constants, statics,
+//! type aliases, tests and whole structs, once for every type in the list,
+//! with `#Name` being the type. The list takes the same types as an
+//! `impl`, so they can be tagged with `mut`, have attributes like
+//! `#[cfg(...)]`, and bring bindings and overrides after a `=>`.
+//! `#[only(...)]`, `#[except(...)]` and the other placeholders work too.
+//! Since every type has the binding it's named by, a `#name` in a template
+//! has to be one of the bindings.
+//! ```
+//! # use impl_twice::impl_twice;
+//! impl_twice!(
+//!     for Int in [u8 => { Wrapper = Wrapped8, sum = sum_u8 },
+//!                 u16 => { Wrapper = Wrapped16, sum = sum_u16 }] {
+//!         #[derive(Clone, Copy, Debug, PartialEq)]
+//!         pub struct #Wrapper(pub #Int);
+//!
+//!         impl #Wrapper {
+//!             pub const NAME: &'static str = #type_name;
+//!         }
+//!
+//!         pub fn #sum(values: &[#Int]) -> #Int {
+//!             values.iter().sum()
+//!         }
+//!
+//!         #[only(u16)]
+//!         pub type Widest = #Wrapper;
+//!     }
+//! );
+//!
+//! assert_eq!(sum_u8(&[1, 2]), 3);
+//! assert_eq!(Widest::NAME, "u16");
+//! assert_eq!(Wrapped8(1), Wrapped8(1));
+//! ```
+//!
+//! # Declaring the types
+//! The two types can be declared from one definition with
+//! `struct_twice!`, so that their fields can't drift apart. The name of
+//! the mutable type goes after the name of the shared one, tagged with
+//! `mut`, and has the same generics. The fields are filled in like a body
+//! of `impl_twice!`, so `#mut` is `mut` in the mutable type, and nothing in
+//! the shared one. Enums work the same way. Both types get the attributes,
+//! except that doc comments stay on the shared type, and `Clone` and
+//! `Copy` are left out of the derives of the mutable type, since mutable
+//! references are neither, however they're written, like
+//! `core::clone::Clone`. An attribute meant for only one of the types
+//! goes after `#[only(...)]` or `#[except(...)]` with the name of the
+//! type, or `mut` for the mutable one. The pair can then be implemented
+//! with `impl_twice!` as usual.
+//! ```
+//! # use impl_twice::{impl_twice, struct_twice};
+//! struct_twice! {
+//!     /// A view into a slice.
+//!     #[derive(core::clone::Clone, Copy, Debug)]
+//!     #[only(Slice)]
+//!     #[derive(PartialEq)]
+//!     #[only(mut)]
+//!     #[must_use]
+//!     pub struct Slice<'a, T>, mut SliceMut {
+//!         items: &'a #mut [T],
+//!     }
+//!
+//!     #[derive(Clone, Debug, PartialEq)]
+//!     pub enum Item<'a>, mut ItemMut {
+//!         Number(&'a #mut i32),
+//!         Empty,
+//!     }
+//! }
+//!
+//! impl_twice!(
+//!     impl<T> Slice<'_, T>, mut SliceMut<'_, T> {
+//!         pub fn first(&#mut self) -> Option<&#mut T> {
+//!             self.items.#if_mut(first_mut, first)()
+//!         }
+//!     }
+//! );
+//!
+//! let mut array = [1, 2];
+//! let mut slice = SliceMut { items: &mut array };
+//! *slice.first().unwrap() = 3;
+//! let copy = Slice { items: &array };
+//! assert_eq!(copy.first(), Some(&3));
+//! assert!(copy == copy.clone());
+//! assert_eq!(Item::Empty.clone(), Item::Empty);
+//! ```
+//!
+//! # Defining the traits
+//! Pairs of traits, like `Storage` and `StorageMut`, can be defined from
+//! one definition with `trait_twice!` in the same way. Bounds after the
+//! name of the mutable trait are supertraits that only it has, so
+//! `mut StorageMut: Storage` makes every `StorageMut` a `Storage` too. The
+//! items of the body can be given to one of the traits with
+//! `#[only(...)]`, like in `impl_twice!`.
+//!
+//! With `#[forward]` on the definition, the traits are also implemented
+//! for references, by calling the methods of the type that is referred
+//! to. The immutable trait is implemented for `&T` and `&mut T`, but the
+//! mutable trait only for `&mut T`, since a `&T` can't give out mutable
+//! access. Every method then has to take `&self` or `&mut self`, and name
+//! its other arguments, so that they can be passed on.
+//! ```
+//! # use impl_twice::trait_twice;
+//! trait_twice! {
+//!     /// Something that stores numbers.
+//!     #[forward]
+//!     pub trait Storage, mut StorageMut: Storage {
+//!         fn #if_mut(get_mut, get)(&#mut self, index: usize) -> Option<&#mut i32>;
+//!
+//!         #[only(Storage)]
+//!         fn len(&self) -> usize;
+//!     }
+//! }
+//!
+//! struct Numbers(Vec<i32>);
+//!
+//! impl Storage for Numbers {
+//!     fn get(&self, index: usize) -> Option<&i32> {
+//!         self.0.get(index)
+//!     }
+//!
+//!     fn len(&self) -> usize {
+//!         self.0.len()
+//!     }
+//! }
+//!
+//! impl StorageMut for Numbers {
+//!     fn get_mut(&mut self, index: usize) -> Option<&mut i32> {
+//!         self.0.get_mut(index)
+//!     }
+//! }
+//!
+//! fn increment_last(mut storage: impl StorageMut) {
+//!     let last = storage.len() - 1;
+//!     *storage.get_mut(last).unwrap() += 1;
+//! }
+//!
+//! let mut numbers = Numbers(vec![1, 2]);
+//! increment_last(&mut numbers);
+//! assert_eq!(numbers.get(1), Some(&3));
+//! ```
+//!
+//! # Functions
+//! Free functions come in pairs too, and `fn_twice!` defines both from
+//! one function. They have the same generics, bounds and where clause,
+//! and the same lifetimes, so `&'a #mut Tree` and `&'a #mut Node` borrow
+//! for as long in both. The name of the mutable function goes after the
+//! name of the immutable one, tagged with `mut`.
+//! ```
+//! # use impl_twice::fn_twice;
+//! pub struct Node {
+//!     pub id: u32,
+//!     pub value: i32,
+//! }
+//!
+//! fn_twice! {
+//!     /// Finds the node with the id.
+//!     pub fn find_node, mut find_node_mut<'a>(nodes: &'a #mut [Node], id: u32) -> Option<&'a #mut Node> {
+//!         nodes.#if_mut(iter_mut, iter)().find(|node| node.id == id)
+//!     }
+//! }
+//!
+//! let mut nodes = [Node { id: 1, value: 2 }, Node { id: 4, value: 3 }];
+//! find_node_mut(&mut nodes, 4).unwrap().value += 1;
+//! assert_eq!(find_node(&nodes, 4).unwrap().value, 4);
+//! ```
+//!
+//! In the body, `#type_name` is the name of the function, and
+//! `#[only(...)]` and `#[except(...)]` on a statement keep it for the
+//! functions they name, with the mutable one also going by `mut`.
+//! ```
+//! # use impl_twice::fn_twice;
+//! # pub struct Node {
+//! #     pub id: u32,
+//! #     pub value: i32,
+//! # }
+//! fn_twice! {
+//!     pub fn node_at, mut node_at_mut(nodes: &#mut [Node], index: usize) -> Result<&#mut Node, &'static str> {
+//!         #[only(node_at_mut)]
+//!         assert_ne!(index, 0, "the first node can't be changed");
+//!         nodes.#if_mut(get_mut, get)(index).ok_or(#type_name)
+//!     }
+//! }
+//!
+//! let mut nodes = [Node { id: 1, value: 2 }, Node { id: 4, value: 3 }];
+//! assert_eq!(node_at(&nodes, 0).unwrap().id, 1);
+//! assert_eq!(node_at_mut(&mut nodes, 2).err(), Some("node_at_mut"));
+//! ```
+//!
+//! With the `paste` feature, the name can be left out, and the mutable
+//! function is named after the immutable one with `_mut` on the end.
+#![cfg_attr(feature = "paste", doc = "```")]
+#![cfg_attr(not(feature = "paste"), doc = "```ignore")]
+//! # use impl_twice::fn_twice;
+//! fn_twice! {
+//!     pub fn last<T>(items: &#mut [T]) -> Option<&#mut T> {
+//!         items.#if_mut(last_mut, last)()
+//!     }
+//! }
+//!
+//! let mut array = [1, 2];
+//! *last_mut(&mut array).unwrap() = 3;
+//! assert_eq!(last(&array), Some(&3));
+//! ```
+//!
+//! # The attribute
+//! With the `attribute` feature, the same thing can be written as an
+//! attribute on an ordinary impl block, which keeps the body readable for
+//! rustfmt and rust-analyzer. The attribute lists the other types, and
+//! takes anything that could come after the first type in `impl_twice!`.
+//! The body has to be valid rust on its own, so it can't use placeholders
+//! like `#mut`, but `#[only(...)]` and the other item attributes work.
+#![cfg_attr(feature = "attribute", doc = "```")]
+#![cfg_attr(not(feature = "attribute"), doc = "```ignore")]
+//! use impl_twice::attribute::impl_twice;
+//!
+//! struct WrappedSlice<'a, T>(&'a [T]);
+//! struct WrappedSliceMut<'a, T>(&'a mut [T]);
+//!
+//! #[impl_twice(WrappedSliceMut<'_, T>)]
+//! impl<T> WrappedSlice<'_, T> {
+//!     pub fn get(&self, index: usize) -> Option<&'_ T> {
+//!         self.0.get(index)
+//!     }
+//!
+//!     #[only(WrappedSliceMut)]
+//!     pub fn get_mut(&mut self, index: usize) -> Option<&'_ mut T> {
+//!         self.0.get_mut(index)
+//!     }
+//! }
+//!
+//! let mut array = [1, 2];
+//! *WrappedSliceMut(&mut array).get_mut(0).unwrap() = 3;
+//! assert_eq!(WrappedSlice(&array).get(0), Some(&3));
+//! ```
+//!
+//! The attribute expands to `::impl_twice::impl_twice!`, so when this
+//! crate is only there under another name, like through a re-export of a
+//! crate of your own, its path goes first, as `crate = path`.
+#![cfg_attr(feature = "attribute", doc = "```")]
+#![cfg_attr(not(feature = "attribute"), doc = "```ignore")]
+//! mod reexport {
+//!     pub(crate) use ::impl_twice as twice;
+//! }
+//!
+//! struct Cursor<'a>(&'a [u8]);
+//! struct CursorMut<'a>(&'a mut [u8]);
+//!
+//! #[reexport::twice::attribute::impl_twice(crate = reexport::twice, CursorMut<'_>)]
+//! impl Cursor<'_> {
+//!     pub fn len(&self) -> usize {
+//!         self.0.len()
+//!     }
+//! }
+//!
+//! assert_eq!(CursorMut(&mut [1, 2]).len(), 2);
+//! ```
+//!
+//! The two structs themselves can come from one definition too. With
+//! `#[twice(mut = ...)]` on the shared struct, the mutable one is declared
+//! next to it, with `&'a mut T` for every field that is a `&'a T`. It
+//! gets the same attributes, except that `Clone` and `Copy` are left out
+//! of its derives.
+#![cfg_attr(feature = "attribute", doc = "```")]
+#![cfg_attr(not(feature = "attribute"), doc = "```ignore")]
+//! use impl_twice::attribute::{impl_twice, twice};
+//!
+//! #[twice(mut = PairMut)]
+//! #[derive(Clone, Copy, Debug)]
+//! pub struct Pair<'a, T> {
+//!     pub first: &'a T,
+//!     pub second: &'a T,
+//!     pub swapped: bool,
+//! }
+//!
+//! #[impl_twice(PairMut<'_, T>)]
+//! impl<T> Pair<'_, T> {
+//!     pub fn left(&self) -> &T {
+//!         if self.swapped { self.second } else { self.first }
+//!     }
+//! }
+//!
+//! let (mut a, mut b) = (1, 2);
+//! let pair = PairMut { first: &mut a, second: &mut b, swapped: true };
+//! *pair.second += 1;
+//! assert_eq!(pair.left(), &3);
+//! ```
+//!
+//! When the methods of the mutable type only differ from the shared ones
+//! by `&` and `&mut`, `#[lift_mut(...)]` writes them from the shared impl.
+//! Every method that takes `&self` and returns references is given to the
+//! mutable type taking `&mut self` and returning `&mut` references, with
+//! `&self` in its body borrowed mutably. Methods that have a `_mut`
+//! counterpart in the standard library, like `get` and `iter`, are renamed
+//! to it, both where they're declared and where they're called on `self`,
+//! and `rename(...)` after the type adds more names. The rest of the items
+//! are the same for both types.
+#![cfg_attr(feature = "attribute", doc = "```")]
+#![cfg_attr(not(feature = "attribute"), doc = "```ignore")]
+//! use impl_twice::attribute::{lift_mut, twice};
+//!
+//! #[twice(mut = GridMut)]
+//! pub struct Grid<'a, T> {
+//!     cells: &'a [T],
+//!     width: usize,
+//! }
+//!
+//! #[lift_mut(GridMut<'_, T>, rename(cell = cell_mut, row = row_mut))]
+//! impl<T> Grid<'_, T> {
+//!     pub fn width(&self) -> usize {
+//!         self.width
+//!     }
+//!
+//!     pub fn row(&self, y: usize) -> &[T] {
+//!         let start = y * self.width;
+//!         &self.cells[start..start + self.width]
+//!     }
+//!
+//!     pub fn cell(&self, x: usize, y: usize) -> Option<&T> {
+//!         self.row(y).get(x)
+//!     }
+//!
+//!     pub fn cell_at(&self, position: &[usize]) -> Option<&T> {
+//!         self.cell(*position.first()?, *position.last()?)
+//!     }
+//! }
+//!
+//! let mut cells = [1, 2, 3, 4];
+//! let mut grid = GridMut { cells: &mut cells, width: 2 };
+//! *grid.cell_mut(1, 1).unwrap() = 5;
+//! grid.row_mut(0).reverse();
+//! assert_eq!(grid.cell_at(&[0, 1]), Some(&mut 3));
+//! assert_eq!(grid.width(), 2);
+//! assert_eq!(cells, [2, 1, 3, 5]);
+//! ```
+//! A method whose lifting would be ambiguous is an error that names it,
+//! such as one that returns a reference to a reference, where either of
+//! them could be the mutable one, or one that calls `get` on something
+//! that may or may not be borrowed from `self`.
+#![cfg_attr(feature = "attribute", doc = "```compile_fail")]
+#![cfg_attr(not(feature = "attribute"), doc = "```ignore")]
+//! # use impl_twice::attribute::lift_mut;
+//! # struct Names<'a>(&'a [&'a str]);
+//! # struct NamesMut<'a>(&'a mut [&'a str]);
+//! #[lift_mut(NamesMut<'_>)]
+//! impl Names<'_> {
+//!     pub fn first(&self) -> Option<&&str> {
+//!         self.0.first()
+//!     }
+//! }
+//! ```
+//! The same goes for a field of `#[twice(mut = ...)]` that starts with
+//! `&&`, since only the outer reference is made mutable, and it needs a
+//! lifetime to be written as `&'a mut &'b T`.
+#![cfg_attr(feature = "attribute", doc = "```compile_fail")]
+#![cfg_attr(not(feature = "attribute"), doc = "```ignore")]
+//! # use impl_twice::attribute::twice;
+//! #[twice(mut = NamesMut)]
+//! pub struct Names<'a> {
+//!     first: &&'a str,
+//! }
+//! ```
+//!
+//! # Errors
+//! Input that doesn't make sense is reported with an error that says what
+//! is wrong, instead of expanding to nothing. For example, an `impl` needs
+//! at least one type to implement on,
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! impl_twice!(
+//!     impl<T> where T: Clone {
+//!         fn nothing_to_implement_on() {}
+//!     }
+//! );
+//! ```
+//! every list of types needs a body after it,
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! # struct Borrowed<'a, T>(&'a T);
+//! # struct BorrowedMut<'a, T>(&'a mut T);
+//! impl_twice!(
+//!     impl<T> Borrowed<'_, T>, BorrowedMut<'_, T>
+//! );
+//! ```
+//! the same type can't be listed twice,
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! # struct Borrowed<'a, T>(&'a T);
+//! impl_twice!(
+//!     impl<T> Borrowed<'_, T>, Borrowed<'_, T> {
+//!         fn get(&self) -> &T {
+//!             self.0
+//!         }
+//!     }
+//! );
+//! ```
+//! and `#[only(...)]` and `#[except(...)]` can't name a type that isn't
+//! listed, which is most likely a typo.
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! # struct Borrowed<'a, T>(&'a T);
+//! # struct BorrowedMut<'a, T>(&'a mut T);
+//! impl_twice!(
+//!     impl<T> Borrowed<'_, T>, BorrowedMut<'_, T> {
+//!         #[only(BorowedMut)]
+//!         fn get_mut(&mut self) -> &mut T {
+//!             self.0
+//!         }
+//!     }
+//! );
+//! ```
+//! A `#name` that isn't a placeholder is most likely a typo as well.
+//! ```compile_fail
+//! # use impl_twice::impl_twice;
+//! # struct Borrowed<'a, T>(&'a T);
+//! # struct BorrowedMut<'a, T>(&'a mut T);
+//! impl_twice!(
+//!     impl<T> Borrowed<'_, T>, BorrowedMut<'_, T> {
+//!         fn name(&self) -> &'static str {
+//!             #type_nam
+//!         }
+//!     }
+//! );
+//! ```
+//! A trailing comma after the last type is fine though. In
+//! `struct_twice!`, the second name has to be tagged with `mut`, since
+//! otherwise both types would be the same.
+//! ```compile_fail
+//! # use impl_twice::struct_twice;
+//! struct_twice! {
+//!     pub struct Slice<'a, T>, SliceMut {
+//!         items: &'a #mut [T],
+//!     }
+//! }
+//! ```
+//! A method that `#[forward]` can't pass on is an error that names it.
+//! ```compile_fail
+//! # use impl_twice::trait_twice;
+//! trait_twice! {
+//!     #[forward]
+//!     pub trait Storage, mut StorageMut {
+//!         fn into_vec(self) -> Vec<i32>;
+//!     }
+//! }
+//! ```
+//!
+
+/// The attribute form of [`impl_twice!`], `#[twice(mut = ...)]` for
+/// declaring the mutable counterpart of a struct, and `#[lift_mut(...)]`
+/// for implementing it from the shared one. They need the `attribute`
+/// feature, and live in a module of their own, since the attribute has the
+/// same name as the macro.
+#[cfg(feature = "attribute")]
+pub mod attribute {
+    pub use impl_twice_macros::{impl_twice, lift_mut, twice};
+}
+
+/// A macro for avoiding code duplication for immutable and mutable types.
+/// Check out the crate level documentation for more information
+#[macro_export]
+macro_rules! impl_twice {
+    () => {};
+    (impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([] [] impl $($rest)*);
+    };
+    (unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([] [] unsafe impl $($rest)*);
+    };
+    (# $($rest:tt)*) => {
+        $crate::__impl_twice_next!([] [] # $($rest)*);
+    };
+    (for $var:ident in [] $($rest:tt)*) => {
+        compile_error!("expected at least one type in the list after `in`");
+    };
+    (for $var:ident in [$($targets:tt)*] { $($body:tt)* } $($extra:tt)*) => {
+        $crate::__impl_twice_target!([] {for $var} [] [] [] [] [] $($targets)* { $($body)* } $($extra)*);
+    };
+    (for $($rest:tt)*) => {
+        compile_error!("expected a template like `for Name in [A, B] { ... }`");
+    };
+    ($($rest:tt)*) => {
+        compile_error!("expected `impl`, `unsafe impl` or `for`, every body in `impl_twice!` needs an `impl` header or a `for` before it");
+    };
+}
+
+/// A macro for declaring an immutable type and its mutable counterpart
+/// from one definition. Check out the crate level documentation for more
+/// information
+#[macro_export]
+macro_rules! struct_twice {
+    () => {};
+    ($($tokens:tt)*) => {
+        $crate::__impl_twice_struct!([] [] [] $($tokens)*);
+    };
+}
+
+/// A macro for defining a trait and its mutable counterpart from one
+/// definition. Check out the crate level documentation for more
+/// information
+#[macro_export]
+macro_rules! trait_twice {
+    () => {};
+    ($($tokens:tt)*) => {
+        $crate::__impl_twice_trait!([] [] [] $($tokens)*);
+    };
+}
+
+/// A macro for defining a function and its mutable counterpart from one
+/// definition. Check out the crate level documentation for more
+/// information
+#[macro_export]
+macro_rules! fn_twice {
+    () => {};
+    ($($tokens:tt)*) => {
+        $crate::__impl_twice_fn!([] [] $($tokens)*);
+    };
+}
+
+// The macros below are the internals of `impl_twice!`. They have to be
+// exported so that `$crate::` paths to them work wherever `impl_twice!`
+// is used, but they are not part of the public api.
+
+/// After a group of targets is done, either another `impl` follows,
+/// or the body that all of the groups share. The attributes before an
+/// `impl` are collected here, and go on every target of that group.
+///
+/// The state is:
+/// [finished groups] [attributes of the next group]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_next {
+    ([$($groups:tt)*] [$($attrs:tt)*] # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)*] [$($attrs)* #[$($attr)*]] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] impl < $($rest:tt)*) => {
+        $crate::__impl_twice_generics!([$($groups)*] [$($attrs)*] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] impl $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] {[$($attrs)*] [] []} [] [] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] unsafe impl < $($rest:tt)*) => {
+        $crate::__impl_twice_generics!([$($groups)*] [$($attrs)*] [unsafe] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] {[$($attrs)*] [unsafe] []} [] [] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)+] $($rest:tt)*) => {
+        compile_error!("expected `impl` or `unsafe impl` after the attributes in `impl_twice!`");
+    };
+    ([$({$head:tt $where_args:tt [$($target:tt)*]})*] [] $body:tt $($extra:tt)*) => {
+        $crate::__impl_twice_targets!([$($($target)*)*] [$($({$head $where_args $target})*)*] $body);
+        $crate::impl_twice!($($extra)*);
+    };
+}
+
+/// Emits the impl blocks of all the targets that share a body, each of
+/// which is told about the others, so that it can tell whether a `#name`
+/// that it doesn't bind is bound by any of them.
+///
+/// The state is:
+/// [all targets] [every target with the header of its group] [the body]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_targets {
+    ($targets:tt [$({$head:tt $where_args:tt $target:tt})*] $body:tt) => {
+        const _: () = {
+            $crate::__impl_twice_duplicates!($targets);
+        };
+        $(
+            $crate::__impl_twice_emit!([const] $head $where_args $target [] $body $targets);
+        )*
+    };
+}
+
+/// Munches the generic parameters of an `impl<...>` header, so that
+/// bounds, lifetime bounds and const generics can be written inline.
+///
+/// The state is:
+/// [finished groups] [attributes of this group] [`unsafe` or nothing]
+/// [generic parameters] [depth]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_generics {
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [] > $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] {[$($attrs)*] [$($unsafety)*] [<$($gen)*>]} [] [] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [<] >> $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] {[$($attrs)*] [$($unsafety)*] [<$($gen)* >>]} [] [] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [$($depth:tt)*] < $($rest:tt)*) => {
+        $crate::__impl_twice_generics!([$($groups)*] [$($attrs)*] [$($unsafety)*] [$($gen)* <] [$($depth)* <] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [$($depth:tt)*] << $($rest:tt)*) => {
+        $crate::__impl_twice_generics!([$($groups)*] [$($attrs)*] [$($unsafety)*] [$($gen)* <<] [$($depth)* < <] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [< $($depth:tt)*] > $($rest:tt)*) => {
+        $crate::__impl_twice_generics!([$($groups)*] [$($attrs)*] [$($unsafety)*] [$($gen)* >] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [< < $($depth:tt)*] >> $($rest:tt)*) => {
+        $crate::__impl_twice_generics!([$($groups)*] [$($attrs)*] [$($unsafety)*] [$($gen)* >>] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [$($depth:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_generics!([$($groups)*] [$($attrs)*] [$($unsafety)*] [$($gen)* $token] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] [$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*] [$($depth:tt)*]) => {
+        compile_error!("unclosed `<` in the generic parameters of an `impl`");
+    };
+}
+
+/// Munches the tokens of a single target, keeping track of how deep
+/// into `<...>` we are, so that `,` and `for` inside of generic arguments
+/// are not mistaken for the end of a trait or type. A group ends at a
+/// `where`, at the next `impl`, `unsafe impl` or attributes, or at the body.
+///
+/// The state is:
+/// [finished groups] {[attributes of this group] [`unsafe` or nothing]
+/// [generics of this group]}
+/// [finished targets of this group] [attributes of this target]
+/// [trait of this target, with the `for`] [type of this target] [depth]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_target {
+    // `for<'a>` is a higher-ranked lifetime, as in `for<'a> fn(&'a T)` or
+    // `dyn for<'a> Fn(&'a T)`, and not the `for` of a trait impl.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [$($depth:tt)*] for < $lifetime:lifetime $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($tr)*] [$($ty)* for < $lifetime] [$($depth)* <] $($rest)*);
+    };
+
+    // There are no placeholders to fill in before the body, so there
+    // would be nothing for `#concat(...)` to paste together.
+    ($groups:tt $head:tt $targets:tt $attrs:tt $tr:tt $ty:tt $depth:tt # concat $($rest:tt)*) => {
+        compile_error!("`#concat(...)` only works in the body, the traits and types of an `impl` have to be written out");
+    };
+
+    // Attributes that only go on this target.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [] [] [] # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)* #[$($attr)*]] [] [] [] $($rest)*);
+    };
+
+    // Mistakes in the list of targets.
+    ([$($groups:tt)*] $head:tt [] [] [] [] [] where $($rest:tt)*) => {
+        compile_error!("expected at least one type to implement on after `impl`");
+    };
+    ([$($groups:tt)*] $head:tt [] [] [] [] [] impl $($rest:tt)*) => {
+        compile_error!("expected at least one type to implement on after `impl`");
+    };
+    ([$($groups:tt)*] $head:tt [] [] [] [] [] unsafe impl $($rest:tt)*) => {
+        compile_error!("expected at least one type to implement on after `impl`");
+    };
+    ([$($groups:tt)*] $head:tt [] [] [] [] [] { $($content:tt)* } $($rest:tt)*) => {
+        compile_error!("expected at least one type to implement on after `impl`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)+] [] [] [] , $($rest:tt)*) => {
+        compile_error!("expected a type after the attributes of a target");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)+] [] [] [] where $($rest:tt)*) => {
+        compile_error!("expected a type after the attributes of a target");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)+] [] [] [] { $($content:tt)* } $($rest:tt)*) => {
+        compile_error!("expected a type after the attributes of a target");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [] [] [] [] , $($rest:tt)*) => {
+        compile_error!("expected a type before `,`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [] [] => $($rest:tt)*) => {
+        compile_error!("expected a type before `=>`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [] [] [] for $($rest:tt)*) => {
+        compile_error!("expected a trait before `for`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)+] [] [] , $($rest:tt)*) => {
+        compile_error!("expected a type after `for`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)+] [] [] where $($rest:tt)*) => {
+        compile_error!("expected a type after `for`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)+] [] [] impl $($rest:tt)*) => {
+        compile_error!("expected a type after `for`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)+] [] [] unsafe impl $($rest:tt)*) => {
+        compile_error!("expected a type after `for`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)+] [] [] # $($rest:tt)*) => {
+        compile_error!("expected a type after `for`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)+] [] [] { $($content:tt)* } $($rest:tt)*) => {
+        compile_error!("expected a type after `for`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)+] [$($ty:tt)+] [] for $($rest:tt)*) => {
+        compile_error!("expected `,` between two targets, found a second `for`");
+    };
+
+    // A trailing comma after the last target.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)+] [] [] [] [] where $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)+] [] [] [] [] impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)*]}] [] impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)+] [] [] [] [] unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)*]}] [] unsafe impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)+] [] [] [] [] { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)*]}] [] { $($content)* } $($rest)*);
+    };
+    // With a trailing comma, attributes of the next `impl` look like the
+    // attributes of a target until the `impl` shows up.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)+] [$($attrs:tt)+] [] [] [] impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)*]}] [$($attrs)*] impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)+] [$($attrs:tt)+] [] [] [] unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)*]}] [$($attrs)*] unsafe impl $($rest)*);
+    };
+
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [] [$($ty:tt)*] [] for $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($ty)* for] [] [] $($rest)*);
+    };
+    // Where predicates that only go on this target. They are kept at the
+    // start of its items until the impl is emitted.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*) , $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [where ($($pred)*)]}] [] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*) where $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [where ($($pred)*)]}] [] [] [] [] where $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*) { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_own_where!([$($targets)*] [$($groups)*] $head [$($targets)*] {[$($attrs)*] [$($tr)*] [$($ty)*]} ($($pred)*) { $($content)* } $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*) impl $($rest:tt)*) => {
+        $crate::__impl_twice_own_where!([$($targets)*] [$($groups)*] $head [$($targets)*] {[$($attrs)*] [$($tr)*] [$($ty)*]} ($($pred)*) impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*) unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_own_where!([$($targets)*] [$($groups)*] $head [$($targets)*] {[$($attrs)*] [$($tr)*] [$($ty)*]} ($($pred)*) unsafe impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*) # $($rest:tt)*) => {
+        $crate::__impl_twice_own_where!([$($targets)*] [$($groups)*] $head [$($targets)*] {[$($attrs)*] [$($tr)*] [$($ty)*]} ($($pred)*) # $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*)) => {
+        $crate::__impl_twice_own_where!([$($targets)*] [$($groups)*] $head [$($targets)*] {[$($attrs)*] [$($tr)*] [$($ty)*]} ($($pred)*));
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] where ($($pred:tt)*) => { $($over:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($tr)*] [$($ty)*] [] => { where ($($pred)*) $($over)* } $($rest)*);
+    };
+    // The items that replace shared items of the same name on this target.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] => { $($over:tt)* } , $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [$($over)*]}] [] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] => { $($over:tt)* } where $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [$($over)*]}] [] [] [] [] where $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] => { $($over:tt)* } impl $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [$($over)*]}] [] [] [] [] impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] => { $($over:tt)* } unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [$($over)*]}] [] [] [] [] unsafe impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] => { $($over:tt)* } # $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [$($over)*]}] [] [] [] [] # $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] => { $($over:tt)* } { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] [$($over)*]}] [] [] [] [] { $($content)* } $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)+] [] => { $($over:tt)* } $($rest:tt)*) => {
+        compile_error!("expected `,` or a body after the items of a target");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [] , $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] []}] [] [] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [] where $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] []}] [] [] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [] impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] []}]}] [] impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [] unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] []}]}] [] unsafe impl $($rest)*);
+    };
+    // Types never contain a `#`, so this is the attributes of the next `impl`.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [] # $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] []}]}] [] # $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [] { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [] [$($targets)* {[$($attrs)*] [$($tr)*] [$($ty)*] []}]}] [] { $($content)* } $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [$($depth:tt)*] < $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($tr)*] [$($ty)* <] [$($depth)* <] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [$($depth:tt)*] << $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($tr)*] [$($ty)* <<] [$($depth)* < <] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [< $($depth:tt)*] > $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($tr)*] [$($ty)* >] [$($depth)*] $($rest)*);
+    };
+    // `>>` is a single token, so `Foo<Vec<T>>` closes two levels at once.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [< < $($depth:tt)*] >> $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($tr)*] [$($ty)* >>] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [$($depth:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_target!([$($groups)*] $head [$($targets)*] [$($attrs)*] [$($tr)*] [$($ty)* $token] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [$($depth:tt)+]) => {
+        compile_error!("unclosed `<` in the last type of `impl_twice!`");
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] []) => {
+        compile_error!("expected a `{ ... }` body after the types in `impl_twice!`");
+    };
+}
+
+/// Decides whether `where (...)` without a `,` after the last target of a
+/// group is the where of that target, or the old parenthesized where of
+/// the whole group. It is the target's own when another target of the
+/// group has predicates of its own, since the old form never had those.
+///
+/// The state is:
+/// [targets left to check] [finished groups] {[attributes of this group]
+/// [`unsafe` or nothing] [generics of this group]}
+/// [finished targets of this group] {[attributes] [trait] [type] of this target}
+/// (where predicates)
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_own_where {
+    ([{$other_attrs:tt $other_tr:tt $other_ty:tt [where $($other_over:tt)*]} $($left:tt)*] $groups:tt $head:tt [$($targets:tt)*] {$attrs:tt $tr:tt $ty:tt} ($($pred:tt)*) $($rest:tt)*) => {
+        $crate::__impl_twice_target!($groups $head [$($targets)* {$attrs $tr $ty [where ($($pred)*)]}] [] [] [] [] $($rest)*);
+    };
+    ([$other:tt $($left:tt)*] $groups:tt $head:tt $targets:tt $target:tt $pred:tt $($rest:tt)*) => {
+        $crate::__impl_twice_own_where!([$($left)*] $groups $head $targets $target $pred $($rest)*);
+    };
+    ([] $groups:tt $head:tt [$($targets:tt)*] {$attrs:tt $tr:tt $ty:tt} $pred:tt $($rest:tt)*) => {
+        $crate::__impl_twice_where!($groups $head [$($targets)* {$attrs $tr $ty []}] [] [] $pred $($rest)*);
+    };
+}
+
+/// Munches a where clause, which ends at the next `impl`, `unsafe impl` or
+/// attributes, or at the body. The old parenthesized form, `where (T: Clone)`, is only
+/// picked when the parentheses are followed by one of those, so that
+/// `where (A, B): Trait` still means a bound on a tuple, and then the
+/// predicates inside of the parentheses are munched like any others.
+///
+/// The state is:
+/// [finished groups] {[attributes of this group] [`unsafe` or nothing]
+/// [generics of this group]}
+/// [targets of this group] [where predicates] [depth]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_where {
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [] [] ($($where_args:tt)*) impl $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [] [] $($where_args)* impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [] [] ($($where_args:tt)*) unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [] [] $($where_args)* unsafe impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [] [] ($($where_args:tt)*) # $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [] [] $($where_args)* # $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [] [] ($($where_args:tt)*) { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [] [] $($where_args)* { $($content)* } $($rest)*);
+    };
+    // A trailing comma is dropped, so that the predicates of a target can
+    // be added after these.
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] $where_args:tt [] , impl $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] $where_args [] impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] $where_args:tt [] , unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] $where_args [] unsafe impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] $where_args:tt [] , # $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] $where_args [] # $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] $where_args:tt [] , { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] $where_args [] { $($content)* } $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [] impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [where $($where_args)*] [$($targets)*]}] [] impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [] unsafe impl $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [where $($where_args)*] [$($targets)*]}] [] unsafe impl $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [] # $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [where $($where_args)*] [$($targets)*]}] [] # $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [] { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_next!([$($groups)* {$head [where $($where_args)*] [$($targets)*]}] [] { $($content)* } $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [$($depth:tt)*] < $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [$($where_args)* <] [$($depth)* <] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [$($depth:tt)*] << $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [$($where_args)* <<] [$($depth)* < <] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [< $($depth:tt)*] > $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [$($where_args)* >] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [< < $($depth:tt)*] >> $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [$($where_args)* >>] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [$($depth:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_where!([$($groups)*] $head [$($targets)*] [$($where_args)* $token] [$($depth)*] $($rest)*);
+    };
+    ([$($groups:tt)*] $head:tt [$($targets:tt)*] [$($where_args:tt)*] [$($depth:tt)*]) => {
+        compile_error!("expected a `{ ... }` body after the where clause in `impl_twice!`");
+    };
+}
+
+/// Reports targets that are listed more than once, since they would just
+/// end up as conflicting impls. Every target gets compared with the ones
+/// after it by a macro that is generated to match only that target, in a
+/// block that keeps the macro out of the scope of the caller. Attributes
+/// are compared too, so the same type may be listed twice under different
+/// `#[cfg]`s.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_duplicates {
+    ([]) => {};
+    ([$first:tt $($rest:tt)*]) => {
+        $crate::__impl_twice_duplicate!(($) $first [$($rest)*]);
+        $crate::__impl_twice_duplicates!([$($rest)*]);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_duplicate {
+    (($dollar:tt) {[$($attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] $over:tt} [$($rest:tt)*]) => {
+        macro_rules! __impl_twice_is_duplicate {
+            ({[$($attrs)*] [$($tr)*] [$($ty)*] $dollar over:tt}) => {
+                compile_error!(concat!(
+                    "`", stringify!($($tr)* $($ty)*), "` is listed more than once in `impl_twice!`"
+                ));
+            };
+            ($dollar($dollar other:tt)*) => {};
+        }
+        $(__impl_twice_is_duplicate!($rest);)*
+    };
+}
+
+/// Emits the impl block of a single target. Inner attributes of the body
+/// are moved out of the way first, since they can't come out of the macro
+/// that walks the items. The where predicates of the target itself go
+/// after the ones of its group. A target tagged with `mut` gets `mut` as
+/// its placeholder context, and the others keep `const`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_emit {
+    ($ctx:tt $head:tt $where_args:tt $target:tt [$($inner:tt)*] { # ! [$($attr:tt)*] $($body:tt)* } $others:tt) => {
+        $crate::__impl_twice_emit!($ctx $head $where_args $target [$($inner)* #![$($attr)*]] { $($body)* } $others);
+    };
+    ($ctx:tt $head:tt [] {$attrs:tt $tr:tt $ty:tt [where ($($pred:tt)*) $($over:tt)*]} $inner:tt $body:tt $others:tt) => {
+        $crate::__impl_twice_emit!($ctx $head [where $($pred)*] {$attrs $tr $ty [$($over)*]} $inner $body $others);
+    };
+    ($ctx:tt $head:tt [where] {$attrs:tt $tr:tt $ty:tt [where ($($pred:tt)*) $($over:tt)*]} $inner:tt $body:tt $others:tt) => {
+        $crate::__impl_twice_emit!($ctx $head [where $($pred)*] {$attrs $tr $ty [$($over)*]} $inner $body $others);
+    };
+    ($ctx:tt $head:tt [where $($where_args:tt)+] {$attrs:tt $tr:tt $ty:tt [where ($($pred:tt)*) $($over:tt)*]} $inner:tt $body:tt $others:tt) => {
+        $crate::__impl_twice_emit!($ctx $head [where $($where_args)*, $($pred)*] {$attrs $tr $ty [$($over)*]} $inner $body $others);
+    };
+    ([const] $head:tt $where_args:tt {$attrs:tt [mut $($tr:tt)+] [$($ty:tt)*] $over:tt} $inner:tt $body:tt $others:tt) => {
+        $crate::__impl_twice_name!(
+            [[mut] $head $where_args {$attrs [$($tr)*] [$($ty)*] $over} $inner $body $others]
+            [mut] [] [] [$($tr)*] [$($ty)*]
+        );
+    };
+    ([const] $head:tt $where_args:tt {$attrs:tt [] [mut $($ty:tt)*] $over:tt} $inner:tt $body:tt $others:tt) => {
+        $crate::__impl_twice_name!(
+            [[mut] $head $where_args {$attrs [] [$($ty)*] $over} $inner $body $others]
+            [mut] [] [] [] [$($ty)*]
+        );
+    };
+    ($ctx:tt $head:tt $where_args:tt {$attrs:tt [$($tr:tt)*] [$($ty:tt)*] $over:tt} $inner:tt $body:tt $others:tt) => {
+        $crate::__impl_twice_name!(
+            [$ctx $head $where_args {$attrs [$($tr)*] [$($ty)*] $over} $inner $body $others]
+            [] [] [] [$($tr)*] [$($ty)*]
+        );
+    };
+}
+
+/// Finds the names of the trait and type of a target, which is the last
+/// identifier before any generic arguments, so `crate::views::Slice<T>`
+/// is named `Slice`. Those are the names that `#[only(...)]` and
+/// `#[except(...)]` can refer to, along with `mut` for targets tagged
+/// with it. The names also make up `#type_name` and `#trait_name`, and a
+/// type without a name, like `[T]`, is written out whole instead.
+///
+/// The state is:
+/// [everything needed to emit the impl] [finished names]
+/// [finished names, by list] [current name] [tokens left to name]...
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_name {
+    ($emit:tt [$($names:tt)*] [$($lists:tt)*] [$($name:tt)*] [< $($tokens:tt)*] $($more:tt)*) => {
+        $crate::__impl_twice_name!($emit [$($names)* $($name)*] [$($lists)* [$($name)*]] [] $($more)*);
+    };
+    ($emit:tt [$($names:tt)*] [$($lists:tt)*] [$($name:tt)*] [<< $($tokens:tt)*] $($more:tt)*) => {
+        $crate::__impl_twice_name!($emit [$($names)* $($name)*] [$($lists)* [$($name)*]] [] $($more)*);
+    };
+    ($emit:tt [$($names:tt)*] [$($lists:tt)*] [$($name:tt)*] [for $($tokens:tt)*] $($more:tt)*) => {
+        $crate::__impl_twice_name!($emit [$($names)* $($name)*] [$($lists)* [$($name)*]] [] $($more)*);
+    };
+    ($emit:tt [$($names:tt)*] [$($lists:tt)*] [$($name:tt)*] [$ident:ident] $($more:tt)*) => {
+        $crate::__impl_twice_name!($emit [$($names)* $ident] [$($lists)* [$ident]] [] $($more)*);
+    };
+    ($emit:tt $names:tt $lists:tt [$($name:tt)*] [$ident:ident $($tokens:tt)*] $($more:tt)*) => {
+        $crate::__impl_twice_name!($emit $names $lists [$ident] [$($tokens)*] $($more)*);
+    };
+    ($emit:tt $names:tt $lists:tt [$($name:tt)*] [$token:tt $($tokens:tt)*] $($more:tt)*) => {
+        $crate::__impl_twice_name!($emit $names $lists [$($name)*] [$($tokens)*] $($more)*);
+    };
+    ($emit:tt [$($names:tt)*] [$($lists:tt)*] [$($name:tt)*] [] $($more:tt)*) => {
+        $crate::__impl_twice_name!($emit [$($names)* $($name)*] [$($lists)* [$($name)*]] [] $($more)*);
+    };
+    // A target without bindings or overrides goes straight to its impl.
+    ([$ctx:tt $head:tt $where_args:tt {$attrs:tt $tr:tt [$($ty:tt)*] []} $inner:tt $body:tt $others:tt] $names:tt [$tr_name:tt []] []) => {
+        $crate::__impl_twice_impl!(
+            ($) {$names [$tr_name [$($ty)*]]} []
+            [$ctx $head $where_args {$attrs $tr [$($ty)*] [] []} $inner $body $others]
+        );
+    };
+    ([$ctx:tt $head:tt $where_args:tt {$attrs:tt $tr:tt $ty:tt []} $inner:tt $body:tt $others:tt] $names:tt $lists:tt []) => {
+        $crate::__impl_twice_impl!(($) {$names $lists} [] [$ctx $head $where_args {$attrs $tr $ty [] []} $inner $body $others]);
+    };
+    ([$ctx:tt $head:tt $where_args:tt {$attrs:tt $tr:tt [$($ty:tt)*] [$($over:tt)*]} $inner:tt $body:tt $others:tt] $names:tt [$tr_name:tt []] []) => {
+        $crate::__impl_twice_bindings!(
+            [$ctx $head $where_args {$attrs $tr [$($ty)*]} $inner $body $others]
+            {$names [$tr_name [$($ty)*]]} [] $($over)*
+        );
+    };
+    ([$ctx:tt $head:tt $where_args:tt {$attrs:tt $tr:tt $ty:tt [$($over:tt)*]} $inner:tt $body:tt $others:tt] $names:tt $lists:tt []) => {
+        $crate::__impl_twice_bindings!(
+            [$ctx $head $where_args {$attrs $tr $ty} $inner $body $others]
+            {$names $lists} [] $($over)*
+        );
+    };
+}
+
+/// Makes `#type_name` out of the name of a type. A type without a name
+/// is passed on as it is, and one that was passed to `impl_twice!` as a
+/// `ty` fragment also looks like that, since its tokens can't be seen
+/// into, so its name is found in its text instead.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_type_name {
+    ($name:ident) => {
+        ::core::stringify!($name)
+    };
+    ($($ty:tt)*) => {
+        $crate::__impl_twice_type_name(::core::stringify!($($ty)*))
+    };
+}
+
+/// Finds the name of a type in its text the way `__impl_twice_name` does
+/// in its tokens, so `&'a views :: Slice < T >` is named `Slice`. A type
+/// without any identifier outside of its brackets, like `[T]`, is named
+/// by its whole text.
+#[doc(hidden)]
+#[must_use]
+pub const fn __impl_twice_type_name(text: &'static str) -> &'static str {
+    let bytes = text.as_bytes();
+    let (mut start, mut end) = (0, 0);
+    let mut lifetime = false;
+    let mut depth = 0_usize;
+    let mut i = 0;
+    while i < bytes.len() && !(depth == 0 && bytes[i] == b'<') {
+        match bytes[i] {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth = depth.saturating_sub(1),
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' if depth == 0 => {
+                if i == 0 || !matches!(bytes[i - 1], b'_' | b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9') {
+                    lifetime = i > 0 && bytes[i - 1] == b'\'';
+                    if !lifetime {
+                        start = i;
+                    }
+                }
+                if !lifetime {
+                    end = i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let (name, _) = bytes.split_at(end);
+    let (_, name) = name.split_at(start);
+    match core::str::from_utf8(name) {
+        Ok(name) if !name.is_empty() => name,
+        _ => text,
+    }
+}
+
+/// Splits the bindings of a target, like `Signed = i8, BITS = 8`, from
+/// the items that override shared items, which come after them.
+///
+/// The state is:
+/// [everything needed to emit the impl] [names of the target]
+/// [bindings that are done]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_bindings {
+    ($emit:tt $names:tt $bindings:tt $name:ident = $($rest:tt)*) => {
+        $crate::__impl_twice_binding!($emit $names $bindings $name [] [] $($rest)*);
+    };
+    ([$ctx:tt $head:tt $where_args:tt {$attrs:tt $tr:tt $ty:tt} $inner:tt $body:tt $others:tt] $names:tt $bindings:tt $($over:tt)*) => {
+        $crate::__impl_twice_overrides!(
+            [$ctx $head $where_args {$attrs $tr $ty [$($over)*] $bindings} $inner $body $others]
+            $names [] [] $($over)*
+        );
+    };
+}
+
+/// Collects the value of a single binding, which ends at a `,` that isn't
+/// inside of generic arguments, or where the overrides begin.
+///
+/// The state is:
+/// [everything needed to emit the impl] [names of the target]
+/// [bindings that are done] [name of the binding] [value so far]
+/// [angle bracket depth]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_binding {
+    ($emit:tt $names:tt [$($bindings:tt)*] $name:ident $value:tt [] , $($rest:tt)*) => {
+        $crate::__impl_twice_bindings!($emit $names [$($bindings)* {$name $value}] $($rest)*);
+    };
+    ($emit:tt $names:tt $bindings:tt $name:ident [$($value:tt)*] [$($depth:tt)*] < $($rest:tt)*) => {
+        $crate::__impl_twice_binding!($emit $names $bindings $name [$($value)* <] [< $($depth)*] $($rest)*);
+    };
+    ($emit:tt $names:tt $bindings:tt $name:ident [$($value:tt)*] [$($depth:tt)*] << $($rest:tt)*) => {
+        $crate::__impl_twice_binding!($emit $names $bindings $name [$($value)* <<] [< < $($depth)*] $($rest)*);
+    };
+    ($emit:tt $names:tt $bindings:tt $name:ident [$($value:tt)*] [< $($depth:tt)*] > $($rest:tt)*) => {
+        $crate::__impl_twice_binding!($emit $names $bindings $name [$($value)* >] [$($depth)*] $($rest)*);
+    };
+    ($emit:tt $names:tt $bindings:tt $name:ident [$($value:tt)*] [< < $($depth:tt)*] >> $($rest:tt)*) => {
+        $crate::__impl_twice_binding!($emit $names $bindings $name [$($value)* >>] [$($depth)*] $($rest)*);
+    };
+    ($emit:tt $names:tt $bindings:tt $name:ident [$($value:tt)*] $depth:tt $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_binding!($emit $names $bindings $name [$($value)* $token] $depth $($rest)*);
+    };
+    ($emit:tt $names:tt [$($bindings:tt)*] $name:ident $value:tt $depth:tt) => {
+        $crate::__impl_twice_bindings!($emit $names [$($bindings)* {$name $value}]);
+    };
+}
+
+/// Finds the names of the items that a target overrides.
+///
+/// The state is:
+/// [everything needed to emit the impl] [names of the target]
+/// [names of the overrides] [how the current item ends, once it's named]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_overrides {
+    ($emit:tt $names:tt [$($over:tt)*] [] fn $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_overrides!($emit $names [$($over)* $name] [fn] $($rest)*);
+    };
+    ($emit:tt $names:tt [$($over:tt)*] [] const $name:ident : $($rest:tt)*) => {
+        $crate::__impl_twice_overrides!($emit $names [$($over)* $name] [item] $($rest)*);
+    };
+    ($emit:tt $names:tt [$($over:tt)*] [] type $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_overrides!($emit $names [$($over)* $name] [item] $($rest)*);
+    };
+    ($emit:tt $names:tt [$($over:tt)*] [$($end:tt)*] ; $($rest:tt)*) => {
+        $crate::__impl_twice_overrides!($emit $names [$($over)*] [] $($rest)*);
+    };
+    ($emit:tt $names:tt [$($over:tt)*] [fn] { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_overrides!($emit $names [$($over)*] [] $($rest)*);
+    };
+    ($emit:tt $names:tt [$($over:tt)*] [$($end:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_overrides!($emit $names [$($over)*] [$($end)*] $($rest)*);
+    };
+    ($emit:tt $names:tt $over:tt [$($end:tt)*]) => {
+        $crate::__impl_twice_impl!(($) $names $over $emit);
+    };
+}
+
+/// Emits the impl block, or the items of a template, of a single target.
+/// A template has the name it loops over bound to the type, as if it was
+/// the first binding of the target, and its items are emitted as they
+/// are, with the attributes of the target on the macro that emits them.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_impl {
+    ($dollar:tt $names:tt $over_names:tt [$ctx:tt {for $var:ident} $where_args:tt {$target_attrs:tt [$($tr:tt)+] [$($ty:tt)*] $($target:tt)*} $($rest:tt)*]) => {
+        compile_error! {
+            concat!("expected a type in the list of `for ", stringify!($var), " in [...]`, not a trait impl like `", stringify!($($tr)* $($ty)*), "`")
+        }
+    };
+    ($dollar:tt $names:tt $over_names:tt [$ctx:tt {for $var:ident} [$($where_args:tt)+] $($rest:tt)*]) => {
+        compile_error!("a template isn't an impl block, so it can't have a `where` clause");
+    };
+    ($dollar:tt $names:tt $over_names:tt [$ctx:tt {for $var:ident} [] $target:tt [$($inner:tt)+] $body:tt $others:tt]) => {
+        compile_error!("a template isn't an impl block, so it can't have inner attributes");
+    };
+    (
+        $dollar:tt $names:tt $over_names:tt
+        [
+            $ctx:tt {for $var:ident} []
+            {[$($target_attrs:tt)*] [] [$($ty:tt)*] [$($over:tt)*] [$($bindings:tt)*]}
+            [] { $($body:tt)* } $others:tt
+        ]
+    ) => {
+        $crate::__impl_twice_scope! {
+            $dollar $names $over_names [{$var [$($ty)*]} $($bindings)*] [$($ty)*] $others
+            [
+                $($target_attrs)*
+                $crate::__impl_twice_items! { [$($over)*] $ctx $($body)* }
+            ]
+        }
+    };
+    (
+        $dollar:tt $names:tt $over_names:tt
+        [
+            $ctx:tt {[$($attrs:tt)*] [$($unsafety:tt)*] [$($gen:tt)*]} [$($where_args:tt)*]
+            {[$($target_attrs:tt)*] [$($tr:tt)*] [$($ty:tt)*] [$($over:tt)*] $bindings:tt}
+            [$($inner:tt)*] { $($body:tt)* } $others:tt
+        ]
+    ) => {
+        const _: () = {
+            $crate::__impl_twice_scope! {
+                $dollar $names $over_names $bindings [$($ty)*] $others
+                [
+                    $($attrs)*
+                    $($target_attrs)*
+                    $($unsafety)* impl $($gen)* $($tr)* $($ty)* $($where_args)* {
+                        $($inner)*
+                        $crate::__impl_twice_items!([$($over)*] $ctx $($body)*);
+                    }
+                ]
+            }
+        };
+    };
+}
+
+/// Emits macros that tell the items of the body whether a list of names
+/// includes this target, whether the names are those of any of the
+/// targets that share the body, whether an item is overridden by this
+/// target, and what the bindings of this target are, including
+/// `#type_name` and `#trait_name`, followed by the tokens that use them.
+/// Comparing identifiers takes a macro that is generated to match them,
+/// so an impl goes in a `const _` block together with its macros, which
+/// keeps them out of the scope of the caller. Items that have to be named
+/// from outside, like those of a template or the types of
+/// `struct_twice!`, can't go in a block, so each of them shadows the
+/// macros of the one before it instead.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_scope {
+    (
+        ($dollar:tt) {[$($name:ident)*] [[$($trait_name:ident)?] [$($type_name:tt)*]]} [$($over_name:ident)*]
+        [$({$bound:ident [$($value:tt)*]})*] [$($ty:tt)*]
+        [$({$other_attrs:tt [$($other_tr:tt)*] [$($other_ty:tt)*] $other_over:tt})*] [$($tokens:tt)*]
+    ) => {
+        // Any token of the targets passes, so that this doesn't have to
+        // find their names again.
+        macro_rules! __impl_twice_is_target {
+            ($dollar filter:ident []) => {};
+            ($dollar filter:ident [, $dollar($dollar rest:tt)*]) => {
+                __impl_twice_is_target!($dollar filter [$dollar($dollar rest)*]);
+            };
+            ($dollar filter:ident [mut $dollar($dollar rest:tt)*]) => {
+                __impl_twice_is_target!($dollar filter [$dollar($dollar rest)*]);
+            };
+            $($(
+                ($dollar filter:ident [$other_tr $dollar($dollar rest:tt)*]) => {
+                    __impl_twice_is_target!($dollar filter [$dollar($dollar rest)*]);
+                };
+            )*)*
+            $($(
+                ($dollar filter:ident [$other_ty $dollar($dollar rest:tt)*]) => {
+                    __impl_twice_is_target!($dollar filter [$dollar($dollar rest)*]);
+                };
+            )*)*
+            ($dollar filter:ident [$dollar unknown:tt $dollar($dollar rest:tt)*]) => {
+                compile_error! {
+                    concat!(
+                        "`", stringify!($dollar unknown), "` in `#[", stringify!($dollar filter),
+                        "(...)]` isn't one of the types or traits of the `impl`"
+                    )
+                }
+            };
+        }
+
+        macro_rules! __impl_twice_is_named {
+            $(
+                ([$name $dollar($dollar rest:tt)*] [$dollar($dollar yes:tt)*] $dollar no:tt) => {
+                    $dollar($dollar yes)*
+                };
+            )*
+            ([$dollar other:tt $dollar($dollar rest:tt)*] $dollar yes:tt $dollar no:tt) => {
+                __impl_twice_is_named!([$dollar($dollar rest)*] $dollar yes $dollar no);
+            };
+            ([] $dollar yes:tt [$dollar($dollar no:tt)*]) => {
+                $dollar($dollar no)*
+            };
+        }
+
+        macro_rules! __impl_twice_is_overridden {
+            $(
+                ($over_name [$dollar($dollar yes:tt)*] $dollar no:tt) => {
+                    $dollar($dollar yes)*
+                };
+            )*
+            ($dollar other:tt $dollar yes:tt [$dollar($dollar no:tt)*]) => {
+                $dollar($dollar no)*
+            };
+        }
+
+        macro_rules! __impl_twice_bound {
+            $(
+                (
+                    $bound $dollar ctx:tt $dollar stack:tt [$dollar($dollar out:tt)*]
+                    $dollar($dollar rest:tt)*
+                ) => {
+                    $crate::__impl_twice_fill! {
+                        $dollar ctx $dollar stack [$dollar($dollar out)* $($value)*]
+                        $dollar($dollar rest)*
+                    }
+                };
+            )*
+            (
+                type_name $dollar ctx:tt [{concat $dollar($dollar frame:tt)*} $dollar($dollar stack:tt)*] [$dollar($dollar out:tt)*]
+                $dollar($dollar rest:tt)*
+            ) => {
+                $crate::__impl_twice_fill! {
+                    $dollar ctx [{concat $dollar($dollar frame)*} $dollar($dollar stack)*] [$dollar($dollar out)* $($type_name)*]
+                    $dollar($dollar rest)*
+                }
+            };
+            (
+                type_name $dollar ctx:tt $dollar stack:tt [$dollar($dollar out:tt)*]
+                $dollar($dollar rest:tt)*
+            ) => {
+                $crate::__impl_twice_fill! {
+                    $dollar ctx $dollar stack [$dollar($dollar out)* $crate::__impl_twice_type_name!($($type_name)*)]
+                    $dollar($dollar rest)*
+                }
+            };
+            $(
+                (
+                    trait_name $dollar ctx:tt [{concat $dollar($dollar frame:tt)*} $dollar($dollar stack:tt)*] [$dollar($dollar out:tt)*]
+                    $dollar($dollar rest:tt)*
+                ) => {
+                    $crate::__impl_twice_fill! {
+                        $dollar ctx [{concat $dollar($dollar frame)*} $dollar($dollar stack)*] [$dollar($dollar out)* $trait_name]
+                        $dollar($dollar rest)*
+                    }
+                };
+                (
+                    trait_name $dollar ctx:tt $dollar stack:tt [$dollar($dollar out:tt)*]
+                    $dollar($dollar rest:tt)*
+                ) => {
+                    $crate::__impl_twice_fill! {
+                        $dollar ctx $dollar stack [$dollar($dollar out)* ::core::stringify!($trait_name)]
+                        $dollar($dollar rest)*
+                    }
+                };
+            )?
+            (trait_name $dollar($dollar rest:tt)*) => {
+                compile_error! {
+                    concat!("`#trait_name` is only there when implementing a trait, and `", stringify!($($ty)*), "` isn't")
+                }
+            };
+            ($dollar name:ident $dollar($dollar rest:tt)*) => {
+                $crate::__impl_twice_unbound! {
+                    [$($bound)*] [$({$other_attrs [$($other_tr)*] [$($other_ty)*] $other_over})*] [$($ty)*]
+                    $dollar name $dollar($dollar rest)*
+                }
+            };
+        }
+
+        $($tokens)*
+    };
+}
+
+/// Fills in the placeholders of the items of the body, and adds the
+/// overrides at the end. The body is split into its items by
+/// `__impl_twice_split!`, so that a long body doesn't add up towards the
+/// recursion limit.
+///
+/// The state is:
+/// [items that override shared items] [placeholder context]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_items {
+    ([$($over:tt)*] $ctx:tt $($body:tt)*) => {
+        $crate::__impl_twice_split! { [items $ctx] $($body)* }
+        $crate::__impl_twice_fill! { $ctx [] [] $($over)* }
+    };
+}
+
+/// Walks the items of a part of the body, which is usually a single item,
+/// looking for `#[only(...)]`, `#[except(...)]` and `#[twice]`. Every item
+/// gets its placeholders filled in by a macro of its own. After an item
+/// marked with `#[twice]`, the placeholder context goes back to what it
+/// was before the item.
+///
+/// The state is:
+/// [placeholder context]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_walk {
+    ([twice $ctx:tt] $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt) => {};
+    ($ctx:tt # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_attrs!($ctx [] [] # [$($attr)*] $($rest)*);
+    };
+    ($ctx:tt $($rest:tt)*) => {
+        $crate::__impl_twice_item!($ctx [] $($rest)*);
+    };
+}
+
+/// Splits the body into its items, or the body of a function into its
+/// statements, and hands each of them to a macro of its own. Going
+/// through the tokens from the start would add a level towards the
+/// recursion limit every few tokens, so they are put in a tree instead,
+/// by `__impl_twice_tree!`, and only the depth of the tree adds up.
+///
+/// The state is:
+/// [`items` or `stmts`, and the placeholder context]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_split {
+    ($kind:tt $($tokens:tt)*) => {
+        $crate::__impl_twice_tree! { $kind $({$tokens [$tokens]})* }
+    };
+}
+
+/// Builds the tree of `__impl_twice_split!`. A node is `{last token
+/// [tokens] children...}`, and a token is a node without children. Every
+/// step puts the nodes together four at a time, leaving the first few,
+/// until there are no more than four, which become the children of the
+/// root.
+///
+/// The state is:
+/// [kind] nodes
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_tree {
+    ($kind:tt) => {};
+    ($kind:tt $node:tt) => {
+        $crate::__impl_twice_node! { $kind [y] [] $node }
+    };
+    ($kind:tt $a:tt $b:tt $($c:tt $($d:tt)?)?) => {
+        $crate::__impl_twice_node! { $kind [y] [] {[] [] $a $b $($c $($d)?)?} }
+    };
+    (
+        $kind:tt
+        $(
+            {$la:tt [$($fa:tt)*] $($ca:tt)*} {$lb:tt [$($fb:tt)*] $($cb:tt)*}
+            {$lc:tt [$($fc:tt)*] $($cc:tt)*} {$ld:tt [$($fd:tt)*] $($cd:tt)*}
+        )+
+    ) => {
+        $crate::__impl_twice_tree! {
+            $kind
+            $({
+                $ld [$($fa)* $($fb)* $($fc)* $($fd)*]
+                {$la [$($fa)*] $($ca)*} {$lb [$($fb)*] $($cb)*}
+                {$lc [$($fc)*] $($cc)*} {$ld [$($fd)*] $($cd)*}
+            })+
+        }
+    };
+    (
+        $kind:tt $x:tt
+        $(
+            {$la:tt [$($fa:tt)*] $($ca:tt)*} {$lb:tt [$($fb:tt)*] $($cb:tt)*}
+            {$lc:tt [$($fc:tt)*] $($cc:tt)*} {$ld:tt [$($fd:tt)*] $($cd:tt)*}
+        )+
+    ) => {
+        $crate::__impl_twice_tree! {
+            $kind $x
+            $({
+                $ld [$($fa)* $($fb)* $($fc)* $($fd)*]
+                {$la [$($fa)*] $($ca)*} {$lb [$($fb)*] $($cb)*}
+                {$lc [$($fc)*] $($cc)*} {$ld [$($fd)*] $($cd)*}
+            })+
+        }
+    };
+    (
+        $kind:tt $x:tt $y:tt
+        $(
+            {$la:tt [$($fa:tt)*] $($ca:tt)*} {$lb:tt [$($fb:tt)*] $($cb:tt)*}
+            {$lc:tt [$($fc:tt)*] $($cc:tt)*} {$ld:tt [$($fd:tt)*] $($cd:tt)*}
+        )+
+    ) => {
+        $crate::__impl_twice_tree! {
+            $kind $x $y
+            $({
+                $ld [$($fa)* $($fb)* $($fc)* $($fd)*]
+                {$la [$($fa)*] $($ca)*} {$lb [$($fb)*] $($cb)*}
+                {$lc [$($fc)*] $($cc)*} {$ld [$($fd)*] $($cd)*}
+            })+
+        }
+    };
+    (
+        $kind:tt $x:tt $y:tt $z:tt
+        $(
+            {$la:tt [$($fa:tt)*] $($ca:tt)*} {$lb:tt [$($fb:tt)*] $($cb:tt)*}
+            {$lc:tt [$($fc:tt)*] $($cc:tt)*} {$ld:tt [$($fd:tt)*] $($cd:tt)*}
+        )+
+    ) => {
+        $crate::__impl_twice_tree! {
+            $kind $x $y $z
+            $({
+                $ld [$($fa)* $($fb)* $($fc)* $($fd)*]
+                {$la [$($fa)*] $($ca)*} {$lb [$($fb)*] $($cb)*}
+                {$lc [$($fc)*] $($cc)*} {$ld [$($fd)*] $($cd)*}
+            })+
+        }
+    };
+}
+
+/// Walks the tree of `__impl_twice_split!` from the root. When a node is
+/// reached, it's known whether an item starts at it, and what the tokens
+/// after it are, up to where the item it's in ends. For every child, the
+/// same is found out by `__impl_twice_part!`, going by the tokens around
+/// it. A token that starts an item is handed on with the tokens after it.
+///
+/// The state is:
+/// [kind] [`y` if an item starts at the node, `n` if not] [tokens after the node]
+/// the node
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_node {
+    ([items $ctx:tt] [y] [$($after:tt)*] {$token:tt $flat:tt}) => {
+        $crate::__impl_twice_walk! { $ctx $token $($after)* }
+    };
+    ([stmts $ctx:tt] [y] [$($after:tt)*] {$token:tt $flat:tt}) => {
+        $crate::__impl_twice_fill! { $ctx [] [] $token $($after)* }
+    };
+    ($kind:tt [n] $after:tt {$token:tt $flat:tt}) => {};
+    // `__impl_twice_part!` found that an item starts at the node.
+    ($kind:tt [? [$($tail:tt)*]] [] {$last:tt $($node:tt)*}) => {
+        $crate::__impl_twice_part! { $kind [y] {$last $($node)*} [] $last $($tail)* }
+    };
+    (
+        $kind:tt $start:tt [$($after:tt)*]
+        {$last:tt $flat:tt {$la:tt [$($fa:tt)*] $($ca:tt)*} {$lb:tt [$($fb:tt)*] $($cb:tt)*}}
+    ) => {
+        $crate::__impl_twice_part! { $kind $start {$la [$($fa)*] $($ca)*} [] $la $($fb)* $($after)* }
+        $crate::__impl_twice_part! { $kind [? [$($after)*]] {$lb [$($fb)*] $($cb)*} [] $la $($fb)* $($after)* }
+    };
+    (
+        $kind:tt $start:tt [$($after:tt)*]
+        {
+            $last:tt $flat:tt {$la:tt [$($fa:tt)*] $($ca:tt)*} {$lb:tt [$($fb:tt)*] $($cb:tt)*}
+            {$lc:tt [$($fc:tt)*] $($cc:tt)*}
+        }
+    ) => {
+        $crate::__impl_twice_part! { $kind $start {$la [$($fa)*] $($ca)*} [] $la $($fb)* $($fc)* $($after)* }
+        $crate::__impl_twice_part! { $kind [? [$($fc)* $($after)*]] {$lb [$($fb)*] $($cb)*} [] $la $($fb)* $($fc)* $($after)* }
+        $crate::__impl_twice_part! { $kind [? [$($after)*]] {$lc [$($fc)*] $($cc)*} [] $lb $($fc)* $($after)* }
+    };
+    (
+        $kind:tt $start:tt [$($after:tt)*]
+        {
+            $last:tt $flat:tt {$la:tt [$($fa:tt)*] $($ca:tt)*} {$lb:tt [$($fb:tt)*] $($cb:tt)*}
+            {$lc:tt [$($fc:tt)*] $($cc:tt)*} {$ld:tt [$($fd:tt)*] $($cd:tt)*}
+        }
+    ) => {
+        $crate::__impl_twice_part! { $kind $start {$la [$($fa)*] $($ca)*} [] $la $($fb)* $($fc)* $($fd)* $($after)* }
+        $crate::__impl_twice_part! { $kind [? [$($fc)* $($fd)* $($after)*]] {$lb [$($fb)*] $($cb)*} [] $la $($fb)* $($fc)* $($fd)* $($after)* }
+        $crate::__impl_twice_part! { $kind [? [$($fd)* $($after)*]] {$lc [$($fc)*] $($cc)*} [] $lb $($fc)* $($fd)* $($after)* }
+        $crate::__impl_twice_part! { $kind [? [$($after)*]] {$ld [$($fd)*] $($cd)*} [] $lc $($fd)* $($after)* }
+    };
+}
+
+/// Finds the tokens after a node of `__impl_twice_split!`, up to where the
+/// item it's in ends, and hands them to the node. An item ends at a `;`,
+/// or at a `{ ... }` that comes right before something that can only
+/// start another item or statement, like `fn`, `let`, an attribute or a
+/// macro call. Other items ending at a `{ ... }` are taken together with
+/// the next one. The tokens are gone through several at a time when none
+/// of them end an item. With `[? ...]`, it first finds out whether an item
+/// starts at the node, by looking at the token right before it, and then
+/// goes on with the tokens after the node.
+///
+/// The state is:
+/// [kind] [whether an item starts at the node] the node [tokens after the node so far]
+/// the token before the rest, the rest
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_part {
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] ; $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } # $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } $name:ident ! $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } fn $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } pub $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } const $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } type $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } unsafe $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } async $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } extern $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } static $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } struct $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } enum $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } trait $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } impl $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } mod $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } use $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } let $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } if $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } match $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } while $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } loop $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    ($kind:tt $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } return $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind $start [$($after)*] $node }
+    };
+    // In the signature of an item, `{ ... }` can be a const generic
+    // argument, like in `impl Trait<{ N }> for Type`.
+    ([stmts $ctx:tt] $start:tt $node:tt [$($after:tt)*] { $($block:tt)* } for $($rest:tt)*) => {
+        $crate::__impl_twice_node! { [stmts $ctx] $start [$($after)*] $node }
+    };
+    // No item starts at the node.
+    ($kind:tt [? [$($tail:tt)*]] {$last:tt $($node:tt)*} [] $($rest:tt)*) => {
+        $crate::__impl_twice_part! { $kind [n] {$last $($node)*} [] $last $($tail)* }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind [$start] [$($after)* ;] $node }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt { $($block:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_part! { $kind [$start] $node [$($after)* { $($block)* }] { $($block)* } $($rest)* }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt $a:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind [$start] [$($after)* $a ;] $node }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt $a:tt { $($block:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_part! { $kind [$start] $node [$($after)* $a { $($block)* }] { $($block)* } $($rest)* }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt $a:tt $b:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind [$start] [$($after)* $a $b ;] $node }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt $a:tt $b:tt { $($block:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_part! { $kind [$start] $node [$($after)* $a $b { $($block)* }] { $($block)* } $($rest)* }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt $a:tt $b:tt $c:tt $($rest:tt)*) => {
+        $crate::__impl_twice_part! { $kind [$start] $node [$($after)* $a $b $c] $c $($rest)* }
+    };
+    ($kind:tt [$start:ident] $node:tt [$($after:tt)*] $prev:tt $($rest:tt)*) => {
+        $crate::__impl_twice_node! { $kind [$start] [$($after)* $($rest)*] $node }
+    };
+}
+
+/// Collects the attributes of an item, keeping the filters apart.
+///
+/// The state is:
+/// [placeholder context] [attributes to keep] [filters]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_attrs {
+    ($ctx:tt [$($attrs:tt)*] [$($filters:tt)*] # [only $names:tt] $($rest:tt)*) => {
+        $crate::__impl_twice_attrs!($ctx [$($attrs)*] [$($filters)* only $names] $($rest)*);
+    };
+    ($ctx:tt [$($attrs:tt)*] [$($filters:tt)*] # [except $names:tt] $($rest:tt)*) => {
+        $crate::__impl_twice_attrs!($ctx [$($attrs)*] [$($filters)* except $names] $($rest)*);
+    };
+    ($ctx:tt [$($attrs:tt)*] [$($filters:tt)*] # [twice] $($rest:tt)*) => {
+        $crate::__impl_twice_attrs!($ctx [$($attrs)*] [$($filters)* twice] $($rest)*);
+    };
+    ($ctx:tt [$($attrs:tt)*] [$($filters:tt)*] # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_attrs!($ctx [$($attrs)* #[$($attr)*]] [$($filters)*] $($rest)*);
+    };
+    ($ctx:tt [$($attrs:tt)*] [$($filters:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_filter!($ctx [$($attrs)*] [$($filters)*] $($rest)*);
+    };
+}
+
+/// Checks the filters of an item one by one, and skips the item as soon
+/// as one of them doesn't include this target.
+///
+/// The state is:
+/// [placeholder context] [attributes of the item]
+/// [filters left]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_filter {
+    ($ctx:tt [$($attrs:tt)*] [] $($rest:tt)*) => {
+        $crate::__impl_twice_item!($ctx [$($attrs)*] $($rest)*);
+    };
+    ($ctx:tt $attrs:tt [twice $($filters:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_filter!([twice $ctx] $attrs [$($filters)*] $($rest)*);
+    };
+    ($ctx:tt $attrs:tt [only ($($names:tt)*) $($filters:tt)*] $($rest:tt)*) => {
+        __impl_twice_is_target!(only [$($names)*]);
+        __impl_twice_is_named!(
+            [$($names)*]
+            [$crate::__impl_twice_filter!($ctx $attrs [$($filters)*] $($rest)*);]
+            [$crate::__impl_twice_skip!($ctx [] $($rest)*);]
+        );
+    };
+    ($ctx:tt $attrs:tt [except ($($names:tt)*) $($filters:tt)*] $($rest:tt)*) => {
+        __impl_twice_is_target!(except [$($names)*]);
+        __impl_twice_is_named!(
+            [$($names)*]
+            [$crate::__impl_twice_skip!($ctx [] $($rest)*);]
+            [$crate::__impl_twice_filter!($ctx $attrs [$($filters)*] $($rest)*);]
+        );
+    };
+}
+
+/// Finds the name of an item, to skip it if this target overrides it.
+/// The usual beginnings of items are matched in one go, anything else
+/// one token at a time.
+///
+/// The state is:
+/// [placeholder context] [the item so far]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_item {
+    ($ctx:tt [$($item:tt)*] fn $name:ident $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $name
+            [$crate::__impl_twice_skip!($ctx [fn] $($rest)*);]
+            [$crate::__impl_twice_copy!($ctx [$($item)* fn $name] [fn] $($rest)*);]
+        );
+    };
+    ($ctx:tt [$($item:tt)*] const $name:ident : $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $name
+            [$crate::__impl_twice_skip!($ctx [item] $($rest)*);]
+            [$crate::__impl_twice_copy!($ctx [$($item)* const $name :] [item] $($rest)*);]
+        );
+    };
+    ($ctx:tt [$($item:tt)*] type $name:ident $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $name
+            [$crate::__impl_twice_skip!($ctx [item] $($rest)*);]
+            [$crate::__impl_twice_copy!($ctx [$($item)* type $name] [item] $($rest)*);]
+        );
+    };
+    ([twice $ctx:tt] [$($item:tt)*] fn # if_mut $($rest:tt)*) => {
+        $crate::__impl_twice_copy!([twice $ctx] [$($item)* fn # if_mut] [fn] $($rest)*);
+    };
+    ([mut] [$($item:tt)*] fn # if_mut ($yes:ident, $no:ident) $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $yes
+            [$crate::__impl_twice_skip!([mut] [fn] $($rest)*);]
+            [$crate::__impl_twice_copy!([mut] [$($item)* fn $yes] [fn] $($rest)*);]
+        );
+    };
+    ([const] [$($item:tt)*] fn # if_mut ($yes:ident, $no:ident) $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $no
+            [$crate::__impl_twice_skip!([const] [fn] $($rest)*);]
+            [$crate::__impl_twice_copy!([const] [$($item)* fn $no] [fn] $($rest)*);]
+        );
+    };
+    // A name made of placeholders, like `#concat(...)` or a binding,
+    // isn't known yet, so the item can't be overridden.
+    ($ctx:tt [$($item:tt)*] fn # $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* fn] [fn] # $($rest)*);
+    };
+    // Items of a template that end at a `{ ... }` like functions do. The
+    // fields of a struct are filled in along with it, and the items of an
+    // impl, trait or module are walked like the items of the body.
+    ($ctx:tt [$($item:tt)*] struct $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* struct] [fn fields] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] enum $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* enum] [fn fields] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] union $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* union] [fn fields] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] trait $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* trait] [fn items] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] impl $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* impl] [fn items] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] mod $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* mod] [fn items] $($rest)*);
+    };
+    // Other items of a template, which end at a `;`.
+    ($ctx:tt [$($item:tt)*] const # $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* const] [item] # $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] type # $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* type] [item] # $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] static $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* static] [item] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] use $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* use] [item] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] $vis:vis fn $name:ident $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $name
+            [$crate::__impl_twice_skip!($ctx [fn] $($rest)*);]
+            [$crate::__impl_twice_copy!($ctx [$($item)* $vis fn $name] [fn] $($rest)*);]
+        );
+    };
+    ($ctx:tt [$($item:tt)*] $vis:vis const $name:ident : $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $name
+            [$crate::__impl_twice_skip!($ctx [item] $($rest)*);]
+            [$crate::__impl_twice_copy!($ctx [$($item)* $vis const $name :] [item] $($rest)*);]
+        );
+    };
+    ($ctx:tt [$($item:tt)*] $vis:vis type $name:ident $($rest:tt)*) => {
+        __impl_twice_is_overridden!(
+            $name
+            [$crate::__impl_twice_skip!($ctx [item] $($rest)*);]
+            [$crate::__impl_twice_copy!($ctx [$($item)* $vis type $name] [item] $($rest)*);]
+        );
+    };
+    ($ctx:tt [$($item:tt)*] ; $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)* ;);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] ! { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)* ! { $($content)* });
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_item!($ctx [$($item)* $token] $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*]) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)*);
+        $crate::__impl_twice_walk!($ctx);
+    };
+}
+
+/// Copies the rest of an item that has been named, which ends at a `;`,
+/// or at a `{ ... }` if it's a function or another item with a body, like
+/// a struct in a template, and hands it to the macro that fills in its
+/// placeholders. It goes several tokens at a time when none of them end
+/// the item. The body of a function is filled in by a macro of its own,
+/// so that it doesn't add to the depth of the walk.
+///
+/// The state is:
+/// [placeholder context] [the item so far]
+/// [`fn` and how to fill in its body, or `item`]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_copy {
+    ($ctx:tt [$($item:tt)*] $end:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)* ;);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] [fn $($how:tt)*] { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [{fn [$($content)*] $($how)*}] [] $($item)*);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] $end:tt $a:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)* $a ;);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] [fn $($how:tt)*] $a:tt { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [{fn [$($content)*] $($how)*}] [] $($item)* $a);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] $end:tt $a:tt $b:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)* $a $b ;);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] [fn $($how:tt)*] $a:tt $b:tt { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [{fn [$($content)*] $($how)*}] [] $($item)* $a $b);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] $end:tt $a:tt $b:tt $c:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)* $a $b $c ;);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] [fn $($how:tt)*] $a:tt $b:tt $c:tt { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [{fn [$($content)*] $($how)*}] [] $($item)* $a $b $c);
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] $end:tt $a:tt $b:tt $c:tt $d:tt $($rest:tt)*) => {
+        $crate::__impl_twice_copy!($ctx [$($item)* $a $b $c $d] $end $($rest)*);
+    };
+    ($ctx:tt [$($item:tt)*] $end:tt $($rest:tt)*) => {
+        $crate::__impl_twice_fill!($ctx [] [] $($item)* $($rest)*);
+        $crate::__impl_twice_walk!($ctx);
+    };
+}
+
+/// Skips a single item, which ends at a `;`, or at a `{ ... }` if it's a
+/// function, a macro call or another item with a body. Until it's known what kind of item it is,
+/// this goes one token at a time, and after that several at a time.
+///
+/// The state is:
+/// [placeholder context]
+/// [`fn`, `item`, or nothing if it's not known yet what kind of item it is]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_skip {
+    ($ctx:tt [] ; $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [] ! { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [] fn $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [fn] $($rest)*);
+    };
+    ($ctx:tt [] const $name:tt : $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [item] $($rest)*);
+    };
+    ($ctx:tt [] type $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [item] $($rest)*);
+    };
+    ($ctx:tt [] struct $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [fn] $($rest)*);
+    };
+    ($ctx:tt [] enum $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [fn] $($rest)*);
+    };
+    ($ctx:tt [] union $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [fn] $($rest)*);
+    };
+    ($ctx:tt [] trait $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [fn] $($rest)*);
+    };
+    ($ctx:tt [] impl $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [fn] $($rest)*);
+    };
+    ($ctx:tt [] mod $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [fn] $($rest)*);
+    };
+    ($ctx:tt [] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx [] $($rest)*);
+    };
+    ($ctx:tt []) => {
+        $crate::__impl_twice_walk!($ctx);
+    };
+    ($ctx:tt $end:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [fn] { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt $end:tt $a:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [fn] $a:tt { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt $end:tt $a:tt $b:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [fn] $a:tt $b:tt { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt $end:tt $a:tt $b:tt $c:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt [fn] $a:tt $b:tt $c:tt { $($content:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx $($rest)*);
+    };
+    ($ctx:tt $end:tt $a:tt $b:tt $c:tt $d:tt $($rest:tt)*) => {
+        $crate::__impl_twice_skip!($ctx $end $($rest)*);
+    };
+    ($ctx:tt $end:tt $($rest:tt)*) => {
+        $crate::__impl_twice_walk!($ctx);
+    };
+}
+
+/// Reports a `#name` that isn't bound for a target. If any of the targets
+/// that share the body has bindings, this one is most likely missing one,
+/// and otherwise the `#name` is no placeholder at all.
+///
+/// The state is:
+/// [bindings of the target] [targets that share the body] [the target]
+/// [the name] [the state of the macro that fills in placeholders]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_unbound {
+    ([] [] $ty:tt $name:ident $($rest:tt)*) => {
+        compile_error! {
+            concat!("`#", stringify!($name), "` isn't a placeholder, and no type has a binding named `", stringify!($name), "`")
+        }
+    };
+    ([] [{$attrs:tt $tr:tt $target:tt [where $where:tt $bound:ident = $($over:tt)*]} $($others:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_unbound!([$bound] [] $($rest)*);
+    };
+    ([] [{$attrs:tt $tr:tt $target:tt [$bound:ident = $($over:tt)*]} $($others:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_unbound!([$bound] [] $($rest)*);
+    };
+    ([] [$other:tt $($others:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_unbound!([] [$($others)*] $($rest)*);
+    };
+    ([$($bound:ident)+] $others:tt [$($ty:tt)*] $name:ident $($rest:tt)*) => {
+        compile_error! {
+            concat!(
+                "`#", stringify!($name), "` isn't bound for `", stringify!($($ty)*),
+                "`, it needs a `", stringify!($name), " = ...` after its `=>`"
+            )
+        }
+    };
+}
+
+/// Handles a `#concat(...)`, whose parentheses are walked into like any
+/// other group, except that `#type_name` and `#trait_name` in them are
+/// identifiers instead of strings. The filled in group is left for the
+/// macro that pastes the identifiers together, which is only there with
+/// the `paste` feature.
+///
+/// The state is:
+/// [the state of the macro that fills in placeholders]
+#[cfg(feature = "paste")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_concat {
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] ( $($content:tt)* ) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{concat [$($out)*] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt $stack:tt $out:tt $($rest:tt)*) => {
+        compile_error!("expected the parts to paste together in parentheses after `#concat`, like `#concat(get _mut)`");
+    };
+}
+
+/// Handles a `#concat(...)`, which is left for the macro that pastes the
+/// identifiers together once the placeholders inside it are filled in.
+/// That macro is only there with the `paste` feature.
+///
+/// The state is:
+/// [the state of the macro that fills in placeholders]
+#[cfg(not(feature = "paste"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_concat {
+    ($($rest:tt)*) => {
+        compile_error! {
+            "`#concat(...)` needs the `paste` feature of `impl_twice`"
+        }
+    };
+}
+
+/// Pastes the identifiers of every `#concat(...)` in the brackets
+/// together, and leaves the tokens after them, which are filled in by
+/// macros of their own, as they are. Without the `paste` feature there is
+/// nothing to paste, so the tokens stay as they are.
+#[cfg(not(feature = "paste"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_paste {
+    ([$($tokens:tt)*] $($rest:tt)*) => {
+        $($tokens)* $($rest)*
+    };
+}
+
+#[cfg(feature = "paste")]
+#[doc(hidden)]
+pub use impl_twice_macros::__impl_twice_paste;
+
+/// Fills in the placeholders of a function body, with a macro for each
+/// statement, so that a long body doesn't add up towards the recursion
+/// limit. The statements are found by `__impl_twice_split!`, after the
+/// inner attributes of the body, which go first.
+///
+/// The state is:
+/// [placeholder context] [the filled in signature] [inner attributes]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_block {
+    ($ctx:tt $sig:tt [$($inner:tt)*] # ! [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_block!($ctx $sig [$($inner)* #![$($attr)*]] $($rest)*);
+    };
+    ($ctx:tt [$($sig:tt)*] [$($inner:tt)*] $($body:tt)*) => {
+        $crate::__impl_twice_paste! {
+            [$($sig)*] { $($inner)* $crate::__impl_twice_split! { [stmts $ctx] $($body)* } }
+        }
+    };
+}
+
+/// Fills in the placeholders of an item. With a `mut` context, `#mut` is
+/// `mut`, `*#mut` is `*mut` and `#if_mut(a, b)` is `a`. With a `const`
+/// context, `#mut` is nothing, `*#mut` is `*const` and `#if_mut(a, b)` is
+/// `b`, and `#if_mut(a)` is `a` or nothing. A `#name` is looked up in the
+/// bindings of the type, whatever the context. What comes out is handed
+/// to the macro that pastes together the identifiers of `#concat(...)`.
+/// An item marked with `#[twice]` is filled in once with each context.
+/// Groups are walked into with a stack, and tokens that can't be
+/// placeholders are copied several at a time.
+///
+/// The state is:
+/// [placeholder context] [stack of the groups we're in] [tokens that are done]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_fill {
+    ([twice $ctx:tt] $stack:tt $out:tt $($tokens:tt)*) => {
+        $crate::__impl_twice_fill! { [const] $stack $out $($tokens)* }
+        $crate::__impl_twice_fill! { [mut] $stack $out $($tokens)* }
+    };
+    // A block that only some of the targets have ends at its braces.
+    ($ctx:tt [] [] # [only $names:tt] { $($content:tt)* } $($rest:tt)+) => {
+        $crate::__impl_twice_fill! { $ctx [] [] # [only $names] { $($content)* } }
+        $crate::__impl_twice_fill! { $ctx [] [] $($rest)* }
+    };
+    ($ctx:tt [] [] # [except $names:tt] { $($content:tt)* } $($rest:tt)+) => {
+        $crate::__impl_twice_fill! { $ctx [] [] # [except $names] { $($content)* } }
+        $crate::__impl_twice_fill! { $ctx [] [] $($rest)* }
+    };
+    // A statement of a function body that only some of the targets have.
+    ($ctx:tt [] [] # [only ($($names:tt)*)] $($rest:tt)*) => {
+        __impl_twice_is_target!(only [$($names)*]);
+        __impl_twice_is_named! { [$($names)*] [$crate::__impl_twice_fill! { $ctx [] [] $($rest)* }] [] }
+    };
+    ($ctx:tt [] [] # [except ($($names:tt)*)] $($rest:tt)*) => {
+        __impl_twice_is_target!(except [$($names)*]);
+        __impl_twice_is_named! { [$($names)*] [] [$crate::__impl_twice_fill! { $ctx [] [] $($rest)* }] }
+    };
+    ($ctx:tt [] [$($out:tt)*]) => {
+        $crate::__impl_twice_paste! { [$($out)*] }
+    };
+    ($ctx:tt [{fn [$($body:tt)*]}] $out:tt) => {
+        $crate::__impl_twice_block!($ctx $out [] $($body)*);
+    };
+    ($ctx:tt [{fn [$($body:tt)*] fields}] [$($out:tt)*]) => {
+        $crate::__impl_twice_fill! { $ctx [] [$($out)*] { $($body)* } }
+    };
+    ($ctx:tt [{fn [$($body:tt)*] items}] [$($out:tt)*]) => {
+        $crate::__impl_twice_paste! {
+            [$($out)*] { $crate::__impl_twice_items!([] $ctx $($body)*); }
+        }
+    };
+    ([$kw:tt] $stack:tt [$($out:tt)*] * # mut $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { [$kw] $stack [$($out)* * $kw] $($rest)* }
+    };
+    ([mut] $stack:tt [$($out:tt)*] # mut $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { [mut] $stack [$($out)* mut] $($rest)* }
+    };
+    ([const] $stack:tt [$($out:tt)*] # mut $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { [const] $stack [$($out)*] $($rest)* }
+    };
+    ([mut] $stack:tt [$($out:tt)*] # if_mut ($yes:tt, $no:tt) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { [mut] $stack [$($out)* $yes] $($rest)* }
+    };
+    ([const] $stack:tt [$($out:tt)*] # if_mut ($yes:tt, $no:tt) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { [const] $stack [$($out)* $no] $($rest)* }
+    };
+    ([mut] $stack:tt [$($out:tt)*] # if_mut ($yes:tt) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { [mut] $stack [$($out)* $yes] $($rest)* }
+    };
+    ([const] $stack:tt [$($out:tt)*] # if_mut ($yes:tt) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { [const] $stack [$($out)*] $($rest)* }
+    };
+    ($ctx:tt $stack:tt $out:tt # concat $($rest:tt)*) => {
+        $crate::__impl_twice_concat! { $ctx $stack $out $($rest)* }
+    };
+    ($ctx:tt $stack:tt $out:tt # $name:ident $($rest:tt)*) => {
+        __impl_twice_bound! { $name $ctx $stack $out $($rest)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] ( $($content:tt)+ ) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{() [$($out)*] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] [ $($content:tt)+ ] $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{[] [$($out)*] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] { $($content:tt)+ } $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{{} [$($out)*] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [{() [$($outer:tt)*] [$($rest:tt)*]} $($stack:tt)*] [$($out:tt)*]) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* ($($out)*)] $($rest)* }
+    };
+    ($ctx:tt [{[] [$($outer:tt)*] [$($rest:tt)*]} $($stack:tt)*] [$($out:tt)*]) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* [$($out)*]] $($rest)* }
+    };
+    ($ctx:tt [{{} [$($outer:tt)*] [$($rest:tt)*]} $($stack:tt)*] [$($out:tt)*]) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* {$($out)*}] $($rest)* }
+    };
+    ($ctx:tt [{concat [$($outer:tt)*] [$($rest:tt)*]} $($stack:tt)*] [$($out:tt)*]) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* # concat ($($out)*)] $($rest)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $a:tt # $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $a] # $($rest)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $a:tt * $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $a] * $($rest)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt ( $($content:tt)+ ) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{() [$($out)* $a] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt [ $($content:tt)+ ] $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{[] [$($out)* $a] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt { $($content:tt)+ } $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{{} [$($out)* $a] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $a:tt $b:tt # $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $a $b] # $($rest)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $a:tt $b:tt * $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $a $b] * $($rest)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt $b:tt ( $($content:tt)+ ) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{() [$($out)* $a $b] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt $b:tt [ $($content:tt)+ ] $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{[] [$($out)* $a $b] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt $b:tt { $($content:tt)+ } $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{{} [$($out)* $a $b] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $a:tt $b:tt $c:tt # $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $a $b $c] # $($rest)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $a:tt $b:tt $c:tt * $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $a $b $c] * $($rest)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt $b:tt $c:tt ( $($content:tt)+ ) $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{() [$($out)* $a $b $c] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt $b:tt $c:tt [ $($content:tt)+ ] $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{[] [$($out)* $a $b $c] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt [$($stack:tt)*] [$($out:tt)*] $a:tt $b:tt $c:tt { $($content:tt)+ } $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx [{{} [$($out)* $a $b $c] [$($rest)*]} $($stack)*] [] $($content)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $a:tt $b:tt $c:tt $d:tt $($rest:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $a $b $c $d] $($rest)* }
+    };
+    // The last few tokens of a group are copied, and the group is closed,
+    // in the same step.
+    ($ctx:tt [] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_paste! { [$($out)* $($rest)*] }
+    };
+    ($ctx:tt [{fn [$($body:tt)*]}] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_block!($ctx [$($out)* $($rest)*] [] $($body)*);
+    };
+    ($ctx:tt [{() [$($outer:tt)*] []}] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_paste! { [$($outer)* ($($out)* $($rest)*)] }
+    };
+    ($ctx:tt [{[] [$($outer:tt)*] []}] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_paste! { [$($outer)* [$($out)* $($rest)*]] }
+    };
+    ($ctx:tt [{{} [$($outer:tt)*] []}] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_paste! { [$($outer)* {$($out)* $($rest)*}] }
+    };
+    ($ctx:tt [{() [$($outer:tt)*] [$($after:tt)*]} $($stack:tt)*] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* ($($out)* $($rest)*)] $($after)* }
+    };
+    ($ctx:tt [{[] [$($outer:tt)*] [$($after:tt)*]} $($stack:tt)*] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* [$($out)* $($rest)*]] $($after)* }
+    };
+    ($ctx:tt [{{} [$($outer:tt)*] [$($after:tt)*]} $($stack:tt)*] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* {$($out)* $($rest)*}] $($after)* }
+    };
+    ($ctx:tt [{concat [$($outer:tt)*] [$($after:tt)*]} $($stack:tt)*] [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_fill! { $ctx [$($stack)*] [$($outer)* # concat ($($out)* $($rest)*)] $($after)* }
+    };
+    ($ctx:tt $stack:tt [$($out:tt)*] $($rest:tt)+) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($out)* $($rest)*] }
+    };
+}
+
+// The macros below are the internals of `struct_twice!`, which declares
+// the two types the same way `impl_twice!` implements its targets.
+
+/// Sorts the attributes of a type into the ones of the immutable type and
+/// the ones of the mutable type, then finds the names of the two types,
+/// and the rest of the definition. Doc comments describe the immutable
+/// type, so the mutable one gets a doc comment of its own, and `Clone`
+/// and `Copy` are left out of its derives, since mutable references are
+/// neither. An attribute after `#[only(...)]` or `#[except(...)]` is kept
+/// aside until the names of the types are known. The two types are
+/// declared with the same definition, one with `#mut` being nothing and
+/// one with it being `mut`, after which the next definition is sorted the
+/// same way.
+///
+/// The state is:
+/// [attributes of the immutable type] [attributes of the mutable type]
+/// [attributes of only one of the types]
+/// {visibility, keyword and names of the types} [the definition so far]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_struct {
+    ($shared:tt $mutable:tt $filtered:tt {$vis:tt $kw:ident $name:ident mut $mut_name:ident} [$($def:tt)*] ; $($rest:tt)*) => {
+        $crate::__impl_twice_pair!($shared $mutable $filtered {$vis $kw $name mut $mut_name} [$($def)* ;]);
+        $crate::__impl_twice_struct!([] [] [] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt {$vis:tt $kw:ident $name:ident mut $mut_name:ident} [$($def:tt)*] { $($fields:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_pair!($shared $mutable $filtered {$vis $kw $name mut $mut_name} [$($def)* { $($fields)* }]);
+        $crate::__impl_twice_struct!([] [] [] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt {$vis:tt $kw:ident $name:ident mut $mut_name:ident} [$($def:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable $filtered {$vis $kw $name mut $mut_name} [$($def)* $token] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt {$vis:tt $kw:ident $name:ident mut $mut_name:ident} $def:tt) => {
+        compile_error! {
+            concat!("expected the fields of `", stringify!($name), "`, followed by a `;` if they are not in `{ ... }`")
+        }
+    };
+    ($shared:tt $mutable:tt $filtered:tt {$vis:tt $kw:ident $name:ident} [$($gen:tt)*] , mut $mut_name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable $filtered {$vis $kw $name mut $mut_name} [$($gen)*] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt {$vis:tt $kw:ident $name:ident} [$($gen:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable $filtered {$vis $kw $name} [$($gen)* $token] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt {$vis:tt $kw:ident $name:ident} $gen:tt) => {
+        compile_error! {
+            concat!("expected `, mut` and the name of the mutable type after `", stringify!($name), "`, like in `struct Slice<'a>, mut SliceMut { ... }`")
+        }
+    };
+    ($shared:tt $mutable:tt [$($filtered:tt)*] # [only $names:tt] # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable [$($filtered)* {only $names [$($attr)*]}] $($rest)*);
+    };
+    ($shared:tt $mutable:tt [$($filtered:tt)*] # [except $names:tt] # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable [$($filtered)* {except $names [$($attr)*]}] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt # [only $names:tt] $($rest:tt)*) => {
+        compile_error!("expected an attribute after `#[only(...)]`, which picks the type that gets it");
+    };
+    ($shared:tt $mutable:tt $filtered:tt # [except $names:tt] $($rest:tt)*) => {
+        compile_error!("expected an attribute after `#[except(...)]`, which picks the type that doesn't get it");
+    };
+    ([$($shared:tt)*] $mutable:tt $filtered:tt # [doc $($doc:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_struct!([$($shared)* #[doc $($doc)*]] $mutable $filtered $($rest)*);
+    };
+    ([$($shared:tt)*] $mutable:tt $filtered:tt # [derive ($($derives:tt)*)] $($rest:tt)*) => {
+        $crate::__impl_twice_derives!([$($shared)* #[derive($($derives)*)]] $mutable $filtered [] [] [] [$($derives)*] $($rest)*);
+    };
+    ([$($shared:tt)*] [$($mutable:tt)*] $filtered:tt # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_struct!([$($shared)* #[$($attr)*]] [$($mutable)* #[$($attr)*]] $filtered $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt $vis:vis struct $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable $filtered {[$vis] struct $name} [] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt $vis:vis enum $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable $filtered {[$vis] enum $name} [] $($rest)*);
+    };
+    ([] [] []) => {};
+    ($shared:tt $mutable:tt $filtered:tt $($rest:tt)*) => {
+        compile_error!("expected a struct or an enum, `struct_twice!` only declares types");
+    };
+}
+
+/// Copies the derives of the immutable type that aren't `Clone` or `Copy`
+/// over to the mutable type. A derive is looked at by the last segment of
+/// its path, so `core::clone::Clone` is left out too, which is why the
+/// path is also kept the other way around.
+///
+/// The state is:
+/// [attributes of the immutable type] [attributes of the mutable type]
+/// [attributes of only one of the types] [derives that are copied]
+/// [the current derive] [the current derive, backwards] [derives left]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_derives {
+    ($shared:tt $mutable:tt $filtered:tt $kept:tt $path:tt [Clone $($backwards:tt)*] [, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_derives!($shared $mutable $filtered $kept [] [] [$($derives)*] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt $kept:tt $path:tt [Copy $($backwards:tt)*] [, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_derives!($shared $mutable $filtered $kept [] [] [$($derives)*] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt [$($kept:tt)*] [$($path:tt)*] $backwards:tt [, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_derives!($shared $mutable $filtered [$($kept)* $($path)*,] [] [] [$($derives)*] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt $kept:tt [$($path:tt)*] [$($backwards:tt)*] [$token:tt $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_derives!(
+            $shared $mutable $filtered $kept [$($path)* $token] [$token $($backwards)*] [$($derives)*] $($rest)*
+        );
+    };
+    // The last derive doesn't need a `,` after it.
+    ($shared:tt $mutable:tt $filtered:tt $kept:tt [$($path:tt)+] $backwards:tt [] $($rest:tt)*) => {
+        $crate::__impl_twice_derives!($shared $mutable $filtered $kept [$($path)*] $backwards [,] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $filtered:tt [] [] [] [] $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared $mutable $filtered $($rest)*);
+    };
+    ($shared:tt [$($mutable:tt)*] $filtered:tt [$($kept:tt)*] [] [] [] $($rest:tt)*) => {
+        $crate::__impl_twice_struct!($shared [$($mutable)* #[derive($($kept)*)]] $filtered $($rest)*);
+    };
+}
+
+/// Declares the two types of `struct_twice!` with the same definition,
+/// each in a scope of its own like the targets of `impl_twice!`, so that
+/// `#type_name` is the name of the type, and the mutable type also goes
+/// by `mut`. Only the definition is filled in, the attributes and the
+/// name go straight to the output, which keeps the depth of the fill
+/// down.
+///
+/// The state is:
+/// [attributes of the immutable type] [attributes of the mutable type]
+/// [attributes of only one of the types]
+/// {visibility, keyword and names of the types} [the definition]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_pair {
+    (
+        [$($shared:tt)*] [$($mutable:tt)*] $filtered:tt
+        {[$($vis:tt)*] $kw:ident $name:ident mut $mut_name:ident} [$($def:tt)*]
+    ) => {
+        $crate::__impl_twice_scope! {
+            ($) {[$name] [[] [$name]]} [] [] [$name]
+            [{[] [] [$name] []} {[] [] [$mut_name] []}]
+            [$crate::__impl_twice_type_attrs!($filtered [$($shared)*] [$($vis)* $kw $name] [const] [] $($def)*);]
+        }
+        $crate::__impl_twice_scope! {
+            ($) {[$mut_name mut] [[] [$mut_name]]} [] [] [$mut_name]
+            [{[] [] [$name] []} {[] [] [$mut_name] []}]
+            [
+                $crate::__impl_twice_type_attrs! {
+                    $filtered
+                    [
+                        #[doc = concat!("The mutable counterpart of [`", stringify!($name), "`].")]
+                        $($mutable)*
+                    ]
+                    [$($vis)* $kw $mut_name] [mut] []
+                    $($def)*
+                }
+            ]
+        }
+    };
+}
+
+/// Adds the attributes marked with `#[only(...)]` or `#[except(...)]` to
+/// a type of `struct_twice!` if they are meant for it, after its other
+/// attributes, and then fills in its definition.
+///
+/// The state is:
+/// [attributes of only one of the types] [attributes of this type]
+/// [visibility, keyword and name of this type] [placeholder context]
+/// [stack of the groups we're in] [the definition]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_type_attrs {
+    ([{only ($($names:tt)*) $attr:tt} $($filtered:tt)*] [$($attrs:tt)*] $($rest:tt)*) => {
+        __impl_twice_is_target!(only [$($names)*]);
+        __impl_twice_is_named! {
+            [$($names)*]
+            [$crate::__impl_twice_type_attrs! { [$($filtered)*] [$($attrs)* # $attr] $($rest)* }]
+            [$crate::__impl_twice_type_attrs! { [$($filtered)*] [$($attrs)*] $($rest)* }]
+        }
+    };
+    ([{except ($($names:tt)*) $attr:tt} $($filtered:tt)*] [$($attrs:tt)*] $($rest:tt)*) => {
+        __impl_twice_is_target!(except [$($names)*]);
+        __impl_twice_is_named! {
+            [$($names)*]
+            [$crate::__impl_twice_type_attrs! { [$($filtered)*] [$($attrs)*] $($rest)* }]
+            [$crate::__impl_twice_type_attrs! { [$($filtered)*] [$($attrs)* # $attr] $($rest)* }]
+        }
+    };
+    ([] [$($attrs:tt)*] [$($item:tt)*] $ctx:tt $stack:tt $($def:tt)*) => {
+        $crate::__impl_twice_fill! { $ctx $stack [$($attrs)* $($item)*] $($def)* }
+    };
+}
+
+// The macros below are the internals of `trait_twice!`, which defines the
+// two traits the way `struct_twice!` declares its types, and forwards them
+// to references when asked to.
+
+/// Sorts the attributes of a trait the way `__impl_twice_struct` does,
+/// with `#[forward]` asking for the forwarding impls, then finds the name
+/// of the trait.
+///
+/// The state is:
+/// [attributes of the immutable trait] [attributes of the mutable trait]
+/// [`forward` or nothing]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_trait {
+    ($shared:tt $mutable:tt [] # [forward] $($rest:tt)*) => {
+        $crate::__impl_twice_trait!($shared $mutable [forward] $($rest)*);
+    };
+    ([$($shared:tt)*] $mutable:tt $forward:tt # [doc $($doc:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_trait!([$($shared)* #[doc $($doc)*]] $mutable $forward $($rest)*);
+    };
+    ([$($shared:tt)*] [$($mutable:tt)*] $forward:tt # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_trait!([$($shared)* #[$($attr)*]] [$($mutable)* #[$($attr)*]] $forward $($rest)*);
+    };
+    ($shared:tt $mutable:tt $forward:tt $vis:vis unsafe trait $name:ident < $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($shared $mutable $forward {[$vis] [unsafe] $name} [] [] [] [start] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $forward:tt $vis:vis trait $name:ident < $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($shared $mutable $forward {[$vis] [] $name} [] [] [] [start] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $forward:tt $vis:vis unsafe trait $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_trait_header!($shared $mutable $forward {[$vis] [unsafe] $name [] []} [] $($rest)*);
+    };
+    ($shared:tt $mutable:tt $forward:tt $vis:vis trait $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_trait_header!($shared $mutable $forward {[$vis] [] $name [] []} [] $($rest)*);
+    };
+    ([] [] []) => {};
+    ($shared:tt $mutable:tt $forward:tt $($rest:tt)*) => {
+        compile_error!("expected a trait, `trait_twice!` only defines traits");
+    };
+}
+
+/// Munches the generic parameters of a trait, keeping track of how deep
+/// into `<...>` we are like `__impl_twice_generics` does, and collects the
+/// names of the parameters, which are the generic arguments of the trait
+/// in the forwarding impls. The parameters are given a trailing `,`, so
+/// that more can follow them.
+///
+/// The state is:
+/// [attributes of the immutable trait] [attributes of the mutable trait]
+/// [`forward` or nothing] {visibility, `unsafe` and name of the trait}
+/// [generic parameters] [names of the parameters] [depth]
+/// [`start` at the start of a parameter]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_trait_generics {
+    ($s:tt $m:tt $f:tt {$vis:tt $unsafety:tt $name:ident} $gen:tt $names:tt [] [start] > $($rest:tt)*) => {
+        $crate::__impl_twice_trait_header!($s $m $f {$vis $unsafety $name $gen $names} [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt {$vis:tt $unsafety:tt $name:ident} [$($gen:tt)*] $names:tt [] [] > $($rest:tt)*) => {
+        $crate::__impl_twice_trait_header!($s $m $f {$vis $unsafety $name [$($gen)* ,] $names} [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt {$vis:tt $unsafety:tt $name:ident} [$($gen:tt)*] $names:tt [<] $start:tt >> $($rest:tt)*) => {
+        $crate::__impl_twice_trait_header!($s $m $f {$vis $unsafety $name [$($gen)* > ,] $names} [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] [$($names:tt)*] [] [start] const $param:ident $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* const $param] [$($names)* $param,] [] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] [$($names:tt)*] [] [start] $param:lifetime $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* $param] [$($names)* $param,] [] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] [$($names:tt)*] [] [start] $param:ident $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* $param] [$($names)* $param,] [] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] $names:tt [$($depth:tt)*] $start:tt < $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* <] $names [$($depth)* <] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] $names:tt [$($depth:tt)*] $start:tt << $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* <<] $names [$($depth)* < <] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] $names:tt [< $($depth:tt)*] $start:tt > $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* >] $names [$($depth)*] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] $names:tt [< < $($depth:tt)*] $start:tt >> $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* >>] $names [$($depth)*] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] $names:tt [] $start:tt , $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* ,] $names [] [start] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($gen:tt)*] $names:tt $depth:tt $start:tt $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_trait_generics!($s $m $f $info [$($gen)* $token] $names $depth [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt $gen:tt $names:tt $depth:tt $start:tt) => {
+        compile_error!("unclosed `<` in the generic parameters of a trait");
+    };
+}
+
+/// Collects the supertraits of the immutable trait, up to the name of the
+/// mutable trait after them.
+///
+/// The state is:
+/// [attributes of the immutable trait] [attributes of the mutable trait]
+/// [`forward` or nothing] {visibility, `unsafe`, name, generic parameters
+/// and their names} [supertraits]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_trait_header {
+    ($s:tt $m:tt $f:tt {$vis:tt $unsafety:tt $name:ident $gen:tt $names:tt} $supers:tt , mut $mut_name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_trait_bounds!($s $m $f {$vis $unsafety $name mut $mut_name $gen $names} $supers [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [$($supers:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_trait_header!($s $m $f $info [$($supers)* $token] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt {$vis:tt $unsafety:tt $name:ident $gen:tt $names:tt} $supers:tt) => {
+        compile_error! {
+            concat!("expected `, mut` and the name of the mutable trait after `", stringify!($name), "`, like in `trait Visit, mut VisitMut { ... }`")
+        }
+    };
+}
+
+/// Collects the supertraits that only the mutable trait has, which come
+/// after a `:`, and the where clause that both traits share, up to the
+/// body. The supertraits of the mutable trait are the ones of the
+/// immutable trait, with these added to them.
+///
+/// The state is:
+/// [attributes of the immutable trait] [attributes of the mutable trait]
+/// [`forward` or nothing] {visibility, `unsafe`, names, generic parameters
+/// and their names} [supertraits] [supertraits of the mutable trait]
+/// [where clause]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_trait_bounds {
+    ($s:tt $m:tt $f:tt $info:tt [$($supers:tt)*] [] $where:tt => $body:tt) => {
+        $crate::__impl_twice_trait_pair!($s $m $f $info [$($supers)*] [$($supers)*] $where $body);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [] [$($extra:tt)+] $where:tt => $body:tt) => {
+        $crate::__impl_twice_trait_pair!($s $m $f $info [] [: $($extra)+] $where $body);
+    };
+    ($s:tt $m:tt $f:tt $info:tt [: $($supers:tt)*] [$($extra:tt)+] $where:tt => $body:tt) => {
+        $crate::__impl_twice_trait_pair!($s $m $f $info [: $($supers)*] [: $($supers)* + $($extra)+] $where $body);
+    };
+    ($s:tt $m:tt $f:tt {$vis:tt $unsafety:tt $name:ident mut $mut_name:ident $gen:tt $names:tt} $supers:tt $extra:tt $where:tt => $body:tt) => {
+        compile_error! {
+            concat!("expected the supertraits of `", stringify!($name), "` to come after a `:`")
+        }
+    };
+    ($s:tt $m:tt $f:tt $info:tt $supers:tt $extra:tt [where $($preds:tt)*] { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_trait_bounds!($s $m $f $info $supers $extra [where $($preds)*] => { $($body)* });
+        $crate::__impl_twice_trait!([] [] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt $supers:tt $extra:tt [where $($preds:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_trait_bounds!($s $m $f $info $supers $extra [where $($preds)* $token] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt $supers:tt [] : $($rest:tt)*) => {
+        $crate::__impl_twice_trait_bounds!($s $m $f $info $supers [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt $supers:tt $extra:tt where $($rest:tt)*) => {
+        $crate::__impl_twice_trait_bounds!($s $m $f $info $supers $extra [where] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt $supers:tt $extra:tt { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_trait_bounds!($s $m $f $info $supers $extra [] => { $($body)* });
+        $crate::__impl_twice_trait!([] [] [] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt $info:tt $supers:tt [$($extra:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_trait_bounds!($s $m $f $info $supers [$($extra)* $token] $($rest)*);
+    };
+    ($s:tt $m:tt $f:tt {$vis:tt $unsafety:tt $name:ident mut $mut_name:ident $gen:tt $names:tt} $($rest:tt)*) => {
+        compile_error! {
+            concat!("expected the body of `", stringify!($name), "` and `", stringify!($mut_name), "`")
+        }
+    };
+}
+
+/// Defines the two traits of `trait_twice!` with the same body, each in
+/// a scope of its own like the types of `struct_twice!`. The items of the
+/// body are walked like the items of `impl_twice!`, so that they can be
+/// given to only one of the traits.
+///
+/// The state is:
+/// [attributes of the immutable trait] [attributes of the mutable trait]
+/// [`forward` or nothing] {visibility, `unsafe`, names, generic parameters
+/// and their names} [supertraits] [supertraits of the mutable trait]
+/// [where clause] { body }
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_trait_pair {
+    (
+        [$($shared:tt)*] [$($mutable:tt)*] $forward:tt
+        {[$($vis:tt)*] [$($unsafety:tt)*] $name:ident mut $mut_name:ident [$($gen:tt)*] $names:tt}
+        [$($supers:tt)*] [$($mut_supers:tt)*] [$($where:tt)*] { $($body:tt)* }
+    ) => {
+        $crate::__impl_twice_scope! {
+            ($) {[$name] [[] [$name]]} [] [] [$name]
+            [{[] [] [$name] []} {[] [] [$mut_name] []}]
+            [
+                $crate::__impl_twice_fill! {
+                    [const] [{fn [$($body)*] items}] [$($shared)* $($vis)* $($unsafety)* trait $name]
+                    <$($gen)*> $($supers)* $($where)*
+                }
+            ]
+        }
+        $crate::__impl_twice_scope! {
+            ($) {[$mut_name] [[] [$mut_name]]} [] [] [$mut_name]
+            [{[] [] [$name] []} {[] [] [$mut_name] []}]
+            [
+                $crate::__impl_twice_fill! {
+                    [mut] [{fn [$($body)*] items}]
+                    [
+                        #[doc = concat!("The mutable counterpart of [`", stringify!($name), "`].")]
+                        $($mutable)*
+                        $($vis)* $($unsafety)* trait $mut_name
+                    ]
+                    <$($gen)*> $($mut_supers)* $($where)*
+                }
+            ]
+        }
+        $crate::__impl_twice_forwards! {
+            $forward {[$($unsafety)*] $name $mut_name [$($gen)*] $names [$($where)*]} { $($body)* }
+        }
+    };
+}
+
+/// Implements the traits of `trait_twice!` for references to the types
+/// that implement them, by calling the methods of the type. The immutable
+/// trait is implemented for `&T` and `&mut T`, but the mutable one only
+/// for `&mut T`, since a `&T` can't lend out mutable access.
+///
+/// The state is:
+/// [`forward` or nothing] {`unsafe`, names, generic parameters and their
+/// names, and where clause of the traits} { body }
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_forwards {
+    ([] $info:tt $body:tt) => {};
+    (
+        [forward] {[$($unsafety:tt)*] $name:ident $mut_name:ident [$($gen:tt)*] [$($names:tt)*] [$($where:tt)*]}
+        { $($body:tt)* }
+    ) => {
+        const _: () = {
+            $crate::__impl_twice_scope! {
+                ($) {[$name] [[$name] [$name]]} [] [] [$name]
+                [{[] [] [$name] []} {[] [] [$mut_name] []}]
+                [
+                    $($unsafety)* impl<'__a, $($gen)* __T: ?Sized + $name<$($names)*>> $name<$($names)*> for &'__a __T
+                    $($where)*
+                    {
+                        $crate::__impl_twice_forward!([const] [$name<$($names)*>] [] $($body)*);
+                    }
+
+                    $($unsafety)* impl<'__a, $($gen)* __T: ?Sized + $name<$($names)*>> $name<$($names)*> for &'__a mut __T
+                    $($where)*
+                    {
+                        $crate::__impl_twice_forward!([const] [$name<$($names)*>] [] $($body)*);
+                    }
+                ]
+            }
+        };
+        const _: () = {
+            $crate::__impl_twice_scope! {
+                ($) {[$mut_name] [[$mut_name] [$mut_name]]} [] [] [$mut_name]
+                [{[] [] [$name] []} {[] [] [$mut_name] []}]
+                [
+                    $($unsafety)* impl<'__a, $($gen)* __T: ?Sized + $mut_name<$($names)*>> $mut_name<$($names)*> for &'__a mut __T
+                    $($where)*
+                    {
+                        $crate::__impl_twice_forward!([mut] [$mut_name<$($names)*>] [] $($body)*);
+                    }
+                ]
+            }
+        };
+    };
+}
+
+/// Walks the items of a forwarded trait, finding where each of them ends
+/// like `__impl_twice_skip` does, several tokens at a time. Each item is
+/// forwarded by a macro of its own, so that it doesn't add to the depth
+/// of the walk. A default body is left out, since the forwarded item
+/// calls the one of the type instead.
+///
+/// The state is:
+/// [placeholder context] [the trait] [attributes of the item]
+/// [the item so far]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_forward {
+    ($ctx:tt $tr:tt [$($attrs:tt)*] # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_forward!($ctx $tr [$($attrs)* #[$($attr)*]] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt unsafe fn $($rest:tt)*) => {
+        $crate::__impl_twice_forward!($ctx $tr $attrs [unsafe fn] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt fn $($rest:tt)*) => {
+        $crate::__impl_twice_forward!($ctx $tr $attrs [fn] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt type $($rest:tt)*) => {
+        $crate::__impl_twice_forward!($ctx $tr $attrs [type] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt const $($rest:tt)*) => {
+        $crate::__impl_twice_forward!($ctx $tr $attrs [const] $($rest)*);
+    };
+    ($ctx:tt $tr:tt []) => {};
+    ($ctx:tt $tr:tt $attrs:tt $item:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs $item);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $item:tt { $($default:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs $item);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [$($item:tt)*] $a:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs [$($item)* $a]);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [$($item:tt)*] $a:tt { $($default:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs [$($item)* $a]);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [$($item:tt)*] $a:tt $b:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs [$($item)* $a $b]);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [$($item:tt)*] $a:tt $b:tt { $($default:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs [$($item)* $a $b]);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [$($item:tt)*] $a:tt $b:tt $c:tt ; $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs [$($item)* $a $b $c]);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [$($item:tt)*] $a:tt $b:tt $c:tt { $($default:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs [$($item)* $a $b $c]);
+        $crate::__impl_twice_forward!($ctx $tr [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [$($item:tt)*] $a:tt $b:tt $c:tt $d:tt $($rest:tt)*) => {
+        $crate::__impl_twice_forward!($ctx $tr $attrs [$($item)* $a $b $c $d] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $($rest:tt)*) => {
+        compile_error!("only methods, associated types and constants of a trait can be forwarded");
+    };
+}
+
+/// Forwards a single item of a trait to the type that implements it. A
+/// method calls the method of the type, with `self` dereferenced, and the
+/// arguments passed on. An associated type or constant is the one of the
+/// type. The forwarded item goes through the same walk as the items of
+/// `impl_twice!`, so `#[only(...)]` and the placeholders work on it.
+///
+/// The state is:
+/// [placeholder context] [the trait] [attributes of the item]
+/// {what kind of item it is, and its name} [the item so far] [depth]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_forward_item {
+    ($ctx:tt [$($tr:tt)*] [$($attrs:tt)*] [type $name:ident $(: $($bounds:tt)*)?]) => {
+        $crate::__impl_twice_walk!($ctx $($attrs)* type $name = <__T as $($tr)*>::$name;);
+    };
+    ($ctx:tt [$($tr:tt)*] [$($attrs:tt)*] [type # $name:ident $(: $($bounds:tt)*)?]) => {
+        $crate::__impl_twice_walk!($ctx $($attrs)* type #$name = <__T as $($tr)*>::#$name;);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [const $name:ident : $($rest:tt)*]) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {const [$name]} [] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [const # $name:ident : $($rest:tt)*]) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {const [# $name]} [] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [unsafe fn $($rest:tt)*]) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {[unsafe] fn} $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt [fn $($rest:tt)*]) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {[] fn} $($rest)*);
+    };
+
+    // The type of a constant goes up to its default value, if it has one.
+    ($ctx:tt [$($tr:tt)*] [$($attrs:tt)*] {const [$($name:tt)*]} [$($ty:tt)*] [] $(= $($default:tt)*)?) => {
+        $crate::__impl_twice_walk!($ctx $($attrs)* const $($name)*: $($ty)* = <__T as $($tr)*>::$($name)*;);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {const $name:tt} [$($ty:tt)*] [$($depth:tt)*] < $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {const $name} [$($ty)* <] [$($depth)* <] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {const $name:tt} [$($ty:tt)*] [$($depth:tt)*] << $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {const $name} [$($ty)* <<] [$($depth)* < <] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {const $name:tt} [$($ty:tt)*] [< $($depth:tt)*] > $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {const $name} [$($ty)* >] [$($depth)*] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {const $name:tt} [$($ty:tt)*] [< < $($depth:tt)*] >> $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {const $name} [$($ty)* >>] [$($depth)*] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {const $name:tt} [$($ty:tt)*] $depth:tt $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {const $name} [$($ty)* $token] $depth $($rest)*);
+    };
+
+    // The name of a method, which can be a placeholder.
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn} # if_mut ($($args:tt)*) $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn [# if_mut ($($args)*)]} [] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn} # concat ($($args:tt)*) $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn [# concat ($($args)*)]} [] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn} # $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn [# $name]} [] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn} $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn [$name]} [] [] $($rest)*);
+    };
+
+    // The generics of a method go up to its arguments.
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn $name:tt} $before:tt [] ($($args:tt)*) $($after:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs {$unsafety fn $name $before [$($after)*]} ($($args)*));
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn $name:tt} [$($before:tt)*] [$($depth:tt)*] < $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn $name} [$($before)* <] [$($depth)* <] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn $name:tt} [$($before:tt)*] [$($depth:tt)*] << $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn $name} [$($before)* <<] [$($depth)* < <] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn $name:tt} [$($before:tt)*] [< $($depth:tt)*] > $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn $name} [$($before)* >] [$($depth)*] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn $name:tt} [$($before:tt)*] [< < $($depth:tt)*] >> $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn $name} [$($before)* >>] [$($depth)*] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn $name:tt} [$($before:tt)*] $depth:tt $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_forward_item!($ctx $tr $attrs {$unsafety fn $name} [$($before)* $token] $depth $($rest)*);
+    };
+
+    ($ctx:tt $tr:tt $attrs:tt $($rest:tt)*) => {
+        compile_error!("expected a method, an associated type or a constant, which are what can be forwarded");
+    };
+}
+
+/// Forwards the arguments of a method. The method has to take `&self` or
+/// `&mut self`, which is dereferenced to call the method of the type, and
+/// the other arguments have to be names, which are passed on.
+///
+/// The state is:
+/// [placeholder context] [the trait] [attributes of the method]
+/// {`unsafe`, name, generics, what comes after the arguments, `self` and
+/// how it's passed on} [arguments so far] [names of the arguments] [depth]
+/// [`start` at the start of an argument]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_forward_args {
+    ($ctx:tt $tr:tt $attrs:tt {$($info:tt)*} (& mut $self:ident $(, $($args:tt)*)?)) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs {$($info)* [& mut $self] [&mut **$self]} [] [] [] [start] $($($args)*)?);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$($info:tt)*} (& # mut $self:ident $(, $($args:tt)*)?)) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs {$($info)* [& # mut $self] [&# mut **$self]} [] [] [] [start] $($($args)*)?);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$($info:tt)*} (& $lifetime:lifetime mut $self:ident $(, $($args:tt)*)?)) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs {$($info)* [& $lifetime mut $self] [&mut **$self]} [] [] [] [start] $($($args)*)?);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$($info:tt)*} (& $lifetime:lifetime # mut $self:ident $(, $($args:tt)*)?)) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs {$($info)* [& $lifetime # mut $self] [&# mut **$self]} [] [] [] [start] $($($args)*)?);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$($info:tt)*} (& $lifetime:lifetime $self:ident $(, $($args:tt)*)?)) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs {$($info)* [& $lifetime $self] [&**$self]} [] [] [] [start] $($($args)*)?);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$($info:tt)*} (& $self:ident $(, $($args:tt)*)?)) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs {$($info)* [& $self] [&**$self]} [] [] [] [start] $($($args)*)?);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn [$($name:tt)*] $before:tt $after:tt} $args:tt) => {
+        compile_error! {
+            concat!("can't forward `", stringify!($($name)*), "`, only methods that take `&self` or `&mut self` can be forwarded")
+        }
+    };
+
+    (
+        $ctx:tt [$($tr:tt)*] [$($attrs:tt)*]
+        {[$($unsafety:tt)*] fn [$($name:tt)*] [$($before:tt)*] [$($after:tt)*] [$($receiver:tt)*] [$($this:tt)*]}
+        [$($args:tt)*] [$($names:tt)*] [] $start:tt
+    ) => {
+        $crate::__impl_twice_walk! {
+            $ctx
+            $($attrs)*
+            $($unsafety)* fn $($name)* $($before)* ($($receiver)*, $($args)*) $($after)* {
+                $($unsafety)* { <__T as $($tr)*>::$($name)*($($this)*, $($names)*) }
+            }
+        }
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] [$($names:tt)*] [] [start] mut $arg:ident : $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* $arg :] [$($names)* $arg,] [] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] [$($names:tt)*] [] [start] $arg:ident : $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* $arg :] [$($names)* $arg,] [] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt {$unsafety:tt fn [$($name:tt)*] $($info:tt)*} $args:tt $names:tt [] [start] $($rest:tt)*) => {
+        compile_error! {
+            concat!("can't forward `", stringify!($($name)*), "`, the arguments of a forwarded method have to be names, so that they can be passed on")
+        }
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] $names:tt [$($depth:tt)*] $start:tt < $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* <] $names [$($depth)* <] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] $names:tt [$($depth:tt)*] $start:tt << $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* <<] $names [$($depth)* < <] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] $names:tt [< $($depth:tt)*] $start:tt > $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* >] $names [$($depth)*] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] $names:tt [< < $($depth:tt)*] $start:tt >> $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* >>] $names [$($depth)*] [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] $names:tt [] $start:tt , $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* ,] $names [] [start] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt [$($args:tt)*] $names:tt $depth:tt $start:tt $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_forward_args!($ctx $tr $attrs $info [$($args)* $token] $names $depth [] $($rest)*);
+    };
+    ($ctx:tt $tr:tt $attrs:tt $info:tt $args:tt $names:tt $depth:tt $start:tt) => {
+        compile_error!("unclosed `<` in the arguments of a method");
+    };
+}
+
+// The macros below are the internals of `fn_twice!`, which defines the two
+// functions the way `struct_twice!` declares its types.
+
+/// Sorts the attributes of a function the way `__impl_twice_struct` does,
+/// then finds the name of the function, the name of its mutable
+/// counterpart if there is one, and the signature, up to the body. What
+/// comes before the `fn`, like `pub` or `const`, is kept as it is.
+///
+/// The state is:
+/// [attributes of the immutable function] [attributes of the mutable
+/// function] {what comes before the `fn`, the name, and the name of the
+/// mutable function} [the signature so far]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_fn {
+    ($shared:tt $mutable:tt {$head:tt $name:ident $mut_name:tt} $sig:tt { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__impl_twice_fn_pair!($shared $mutable {$head $name $mut_name} $sig { $($body)* });
+        $crate::__impl_twice_fn!([] [] $($rest)*);
+    };
+    ($shared:tt $mutable:tt {$head:tt $name:ident $mut_name:tt} [$($sig:tt)*] $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_fn!($shared $mutable {$head $name $mut_name} [$($sig)* $token] $($rest)*);
+    };
+    ($shared:tt $mutable:tt {$head:tt $name:ident $mut_name:tt} $sig:tt) => {
+        compile_error! {
+            concat!("expected the body of `", stringify!($name), "`")
+        }
+    };
+    ($shared:tt $mutable:tt {[$($head:tt)*]} fn $name:ident , mut $mut_name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_fn!($shared $mutable {[$($head)*] $name [$mut_name]} [] $($rest)*);
+    };
+    ($shared:tt $mutable:tt {[$($head:tt)*]} fn $name:ident $($rest:tt)*) => {
+        $crate::__impl_twice_fn!($shared $mutable {[$($head)*] $name []} [] $($rest)*);
+    };
+    ($shared:tt $mutable:tt {[$($head:tt)*]} $token:tt $($rest:tt)*) => {
+        $crate::__impl_twice_fn!($shared $mutable {[$($head)* $token]} $($rest)*);
+    };
+    ($shared:tt $mutable:tt {$head:tt}) => {
+        compile_error!("expected a function, `fn_twice!` only defines functions");
+    };
+    ([$($shared:tt)*] $mutable:tt # [doc $($doc:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_fn!([$($shared)* #[doc $($doc)*]] $mutable $($rest)*);
+    };
+    ([$($shared:tt)*] [$($mutable:tt)*] # [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__impl_twice_fn!([$($shared)* #[$($attr)*]] [$($mutable)* #[$($attr)*]] $($rest)*);
+    };
+    ([] []) => {};
+    ($shared:tt $mutable:tt $($rest:tt)*) => {
+        $crate::__impl_twice_fn!($shared $mutable {[]} $($rest)*);
+    };
+}
+
+/// Defines the two functions of `fn_twice!` with the same signature and
+/// body, each in a scope of its own like the types of `struct_twice!`.
+/// The body is filled in statement by statement, like the body of a
+/// method in `impl_twice!`. The mutable function is named by its own name
+/// and `mut`, but a name pasted together with `_mut` is only known once
+/// it's pasted, so then it's just `mut`.
+///
+/// The state is:
+/// [attributes of the immutable function] [attributes of the mutable
+/// function] {what comes before the `fn`, the name, and the name of the
+/// mutable function, with the names it goes by and its `#type_name`}
+/// [the signature] { body }
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_fn_pair {
+    ($shared:tt $mutable:tt {$head:tt $name:ident []} $sig:tt $body:tt) => {
+        $crate::__impl_twice_fn_name!($shared $mutable {$head $name} $sig $body);
+    };
+    ($shared:tt $mutable:tt {$head:tt $name:ident [$mut_name:ident]} $sig:tt $body:tt) => {
+        $crate::__impl_twice_fn_pair!($shared $mutable {$head $name [$mut_name] [$mut_name mut] [$mut_name]} $sig $body);
+    };
+    (
+        [$($shared:tt)*] [$($mutable:tt)*]
+        {[$($head:tt)*] $name:ident [$($mut_name:tt)+] [$($mut_names:ident)+] [$($mut_type_name:tt)+]}
+        [$($sig:tt)*] { $($body:tt)* }
+    ) => {
+        $crate::__impl_twice_scope! {
+            ($) {[$name] [[] [$name]]} [] [] [$name]
+            [{[] [] [$name] []} {[] [] [$($mut_names)*] []}]
+            [$crate::__impl_twice_fill!([const] [{fn [$($body)*]}] [$($shared)* $($head)* fn $name] $($sig)*);]
+        }
+        $crate::__impl_twice_scope! {
+            ($) {[$($mut_names)*] [[] [$($mut_type_name)*]]} [] [] [$($mut_name)*]
+            [{[] [] [$name] []} {[] [] [$($mut_names)*] []}]
+            [
+                $crate::__impl_twice_fill! {
+                    [mut] [{fn [$($body)*]}]
+                    [
+                        #[doc = concat!("The mutable counterpart of [`", stringify!($name), "`].")]
+                        $($mutable)*
+                        $($head)* fn
+                    ]
+                    $($mut_name)+ $($sig)*
+                }
+            ]
+        }
+    };
+}
+
+/// Names the mutable function of `fn_twice!` after the immutable one,
+/// with `_mut` pasted onto it. That takes the `paste` feature.
+///
+/// The state is:
+/// [the state of the macro that defines the two functions]
+#[cfg(feature = "paste")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_fn_name {
+    ($shared:tt $mutable:tt {$head:tt $name:ident} $sig:tt $body:tt) => {
+        $crate::__impl_twice_fn_pair!(
+            $shared $mutable {$head $name [# concat ($name _mut)] [mut] [# concat ($name _mut)]} $sig $body
+        );
+    };
+}
+
+/// Names the mutable function of `fn_twice!` after the immutable one,
+/// with `_mut` pasted onto it. That takes the `paste` feature.
+///
+/// The state is:
+/// [the state of the macro that defines the two functions]
+#[cfg(not(feature = "paste"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_twice_fn_name {
+    ($shared:tt $mutable:tt {$head:tt $name:ident} $sig:tt $body:tt) => {
+        compile_error! {
+            concat!(
+                "the mutable counterpart of `", stringify!($name), "` needs a name, like in `fn ",
+                stringify!($name), ", mut ", stringify!($name), "_mut(...)`, or the `paste` feature of `impl_twice` to be named `",
+                stringify!($name), "_mut`"
+            )
+        }
     };
 }